@@ -1,6 +1,6 @@
 // mod parse_prt2;
 mod parse_prt3;
-// mod parser;
+mod parser;
 // mod parser_combinator;
 // mod practice_for_base;
 // eaa001e
@@ -1,3 +1,15 @@
+#![allow(
+    dead_code,
+    unused_variables,
+    unused_mut,
+    unreachable_patterns,
+    clippy::needless_return,
+    clippy::len_zero,
+    clippy::iter_nth_zero,
+    clippy::clone_on_copy,
+    clippy::redundant_closure,
+    clippy::let_unit_value
+)]
 #[derive(Clone, Debug, PartialEq)]
 
 pub enum ParseObj {
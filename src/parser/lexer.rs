@@ -0,0 +1,1143 @@
+use super::{ParseErr, ParseObj, ParseResult};
+
+// 词法分析: 字符层面的组合子和各种字面量的解析都在这里，
+// tokenize 把源码切成带 span 的 token，语法规则只和 token 打交道，不用再管空白和注释
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    // 字节偏移，[start, end)
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident,
+    Keyword,
+    Int,
+    Float,
+    Str,
+    Char,
+    Punct,
+    Op,
+    // 循环标签 'outer，text 里带着前面的 '
+    Label,
+    // `///` 文档注释，留给语法规则挂到后面的声明上
+    DocComment,
+    // 结尾总有一个 Eof，规则拿到的 token 切片不会是空的
+    Eof,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    // 源码原文；DocComment 是去掉 `///` 后的内容
+    pub text: String,
+    pub span: Span,
+}
+
+// 多字符的排在前面，按最长匹配
+const OPERATORS: [&str; 23] = [
+    "..=", "..", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "%=", "+", "-",
+    "*", "/", "%", "<", ">", "!", "=",
+];
+const PUNCTUATION: [&str; 12] = ["::", "(", ")", "{", "}", "[", "]", ",", ";", ":", ".", "?"];
+
+// 按字节位置往前走，交给字面量解析器的只是当前 token 可能占到的那一段，
+// 不会每个 token 都把剩下的整个源码复制一遍
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ParseErr> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < input.len() {
+        let start = pos;
+        let rest = &input[pos..];
+        let located = |err| offset_err(err, start as u64);
+
+        // 没闭合的块注释要报错，不能当成 `/` 和 `*`
+        if rest.starts_with("/*") {
+            pos += block_comment_len(rest).map_err(located)?;
+            continue;
+        }
+        if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            if let (_, ParseObj::DocComment(text)) = line_comment(rest[..end].to_string())? {
+                tokens.push(Token {
+                    kind: TokenKind::DocComment,
+                    text,
+                    span: Span {
+                        start,
+                        end: start + end,
+                    },
+                });
+            }
+            pos += end;
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap();
+        if whitespace_char(c.to_string()).is_ok() {
+            pos += c.len_utf8();
+            continue;
+        }
+        let starts_number =
+            c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()));
+        // (交给解析器的那一段, 解析器没用到的部分) -> 这个 token 的长度
+        let used = |literal: &str, remains: String| literal.len() - remains.len();
+        let (len, kind) = if c == '"' || rest.starts_with("r\"") || rest.starts_with("r#") {
+            let literal = &rest[..string_end(rest)];
+            let (r, _) = string(literal.to_string()).map_err(located)?;
+            (used(literal, r), TokenKind::Str)
+        } else if c == '\'' {
+            let literal = &rest[..char_end(rest)];
+            match char_literal(literal.to_string()) {
+                Ok((r, _)) => (used(literal, r), TokenKind::Char),
+                // 'outer 这种后面没有闭合引号的是循环标签，只能写在 'outer: 或者 break / continue 后面，
+                // 其它地方还是没写完的字符字面量
+                Err(err) => {
+                    let label_end = 1 + ident_end(&rest[1..]);
+                    let after_jump = tokens.last().is_some_and(|t: &Token| {
+                        t.kind == TokenKind::Keyword && (t.text == "break" || t.text == "continue")
+                    });
+                    let is_label = !rest[label_end..].starts_with('\'')
+                        && (after_jump || rest[label_end..].trim_start().starts_with(':'));
+                    match word(rest[1..label_end].to_string()) {
+                        Ok((_, ParseObj::Ident(_))) if is_label => (label_end, TokenKind::Label),
+                        _ => return Err(located(err)),
+                    }
+                }
+            }
+        } else if starts_number {
+            // 0..10 是区间，不是写坏了的浮点数: 整数后面紧跟 .. 时只把整数部分交给 number，
+            // 1.max(2) 是方法调用: 整数后面的 . 跟着名字时也一样，
+            // 但 1.e / 1.e5 / 1.E+3 像是写坏了的指数，还是交给 number 报错
+            let int_end = ident_end(rest);
+            let after_dot = rest[int_end..].strip_prefix('.');
+            let literal = if after_dot.is_some_and(|s| {
+                s.starts_with('.') || (s.starts_with(is_ident_start) && !is_exponent_like(s))
+            }) {
+                &rest[..int_end]
+            } else {
+                &rest[..number_end(rest)]
+            };
+            // 负号是单独的 token，9223372036854775808 只有跟在 - 后面才合法，
+            // 所以这里带上负号再试一次，真正的范围检查留给语法规则里的 literal
+            let (r, value) = number(literal.to_string())
+                .or_else(|err| number(format!("-{}", literal)).map_err(|_| err))
+                .map_err(located)?;
+            let kind = match value {
+                ParseObj::Float(_) => TokenKind::Float,
+                _ => TokenKind::Int,
+            };
+            (used(literal, r), kind)
+        } else if is_ident_start(c) {
+            let literal = &rest[..ident_end(rest)];
+            match word(literal.to_string())? {
+                (r, ParseObj::Keyword(_)) => (used(literal, r), TokenKind::Keyword),
+                (r, _) => (used(literal, r), TokenKind::Ident),
+            }
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            (op.len(), TokenKind::Op)
+        } else if let Some(p) = PUNCTUATION.iter().find(|p| rest.starts_with(*p)) {
+            (p.len(), TokenKind::Punct)
+        } else {
+            return Err(ParseErr::Unexpected(
+                "token".to_string(),
+                c.to_string(),
+                start as u64,
+            ));
+        };
+
+        pos += len;
+        tokens.push(Token {
+            kind,
+            text: input[start..pos].to_string(),
+            span: Span { start, end: pos },
+        });
+    }
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        text: String::new(),
+        span: Span {
+            start: input.len(),
+            end: input.len(),
+        },
+    });
+    return Ok(tokens);
+}
+
+// 下面几个只估计 token 最多能占到哪里，真正的检查和求值还是交给对应的解析器
+
+fn ident_end(s: &str) -> usize {
+    return s.find(|c: char| !is_ident_char(c)).unwrap_or(s.len());
+}
+
+// 数字里可能出现的字符: 标识符字符（数字、后缀、0x 之类）、小数点，以及 e/E 后面的正负号
+// . 后面的名字是 e / E 加上可选的数字
+fn is_exponent_like(s: &str) -> bool {
+    let name = &s[..ident_end(s)];
+    return name.starts_with(['e', 'E'])
+        && name[1..].chars().all(|c| c.is_ascii_digit() || c == '_');
+}
+
+fn number_end(s: &str) -> usize {
+    let mut prev = ' ';
+    for (i, c) in s.char_indices() {
+        let exp_sign = (c == '+' || c == '-') && (prev == 'e' || prev == 'E');
+        if !(is_ident_char(c) || c == '.' || exp_sign) {
+            return i;
+        }
+        prev = c;
+    }
+    return s.len();
+}
+
+// 到结束引号为止，没有闭合就是剩下的全部（反正要报错）
+fn string_end(s: &str) -> usize {
+    if let Some(after_r) = s.strip_prefix('r') {
+        let hashes = after_r.len() - after_r.trim_start_matches('#').len();
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let body_start = 1 + hashes + 1;
+        return match s.get(body_start..).and_then(|body| body.find(&terminator)) {
+            Some(end) => body_start + end + terminator.len(),
+            None => s.len(),
+        };
+    }
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            return i + 1;
+        }
+    }
+    return s.len();
+}
+
+// 转义序列: \ 加一个字符，\u 再加上 {hex} 和它后面的一个字符（出错时报告用）
+fn escape_end(s: &str) -> usize {
+    let mut chars = s.char_indices().skip(1);
+    let end = match chars.next() {
+        Some((_, 'u')) => chars
+            .find(|&(_, c)| !(c == '{' || c.is_ascii_hexdigit()))
+            .map(|(i, c)| i + c.len_utf8()),
+        Some((i, c)) => Some(i + c.len_utf8()),
+        None => None,
+    };
+    return end.unwrap_or(s.len());
+}
+
+// ' 加一个字符或转义，再加上结束引号的位置（不是引号时出错报告它）
+fn char_end(s: &str) -> usize {
+    let body = &s[1..];
+    let c_end = if body.starts_with('\\') {
+        escape_end(body)
+    } else {
+        body.chars().next().map_or(0, |c| c.len_utf8())
+    };
+    return 1 + c_end + body[c_end..].chars().next().map_or(0, |c| c.len_utf8());
+}
+
+fn zero_or_more(parser: impl Fn(String) -> ParseResult) -> impl Fn(String) -> ParseResult {
+    return move |mut input: String| {
+        let mut result = Vec::new();
+        while let Ok((remains, parsed)) = parser(input.clone()) {
+            input = remains;
+            result.push(parsed);
+        }
+        return Ok((input.clone(), ParseObj::List(result)));
+    };
+}
+
+fn whitespace_char(input: String) -> ParseResult {
+    let sp = parse_char(' ');
+    let tab = parse_char('\t');
+    let newline = parse_char('\n');
+    let carriage_return = parse_char('\r');
+    return any_of(vec![sp, tab, newline, carriage_return])(input);
+}
+
+// `//` 行注释，到换行为止（换行本身留给 whitespace_char）
+// `///` 是文档注释，内容作为 DocComment 保留下来；`////` 还是普通注释
+fn line_comment(input: String) -> ParseResult {
+    if !input.starts_with("//") {
+        return Err(ParseErr::Unexpected(
+            "//".to_string(),
+            next_found(&input),
+            0,
+        ));
+    }
+    let end = input.find('\n').unwrap_or(input.len());
+    let text = input[2..end].trim_end_matches('\r');
+    let remains = input[end..].to_string();
+    if text.starts_with('/') && !text.starts_with("//") {
+        return Ok((remains, ParseObj::DocComment(text[1..].to_string())));
+    }
+    return Ok((remains, ParseObj::Empty));
+}
+
+// 块注释 /* ... */ 的长度，可以嵌套，input 以 /* 开头
+fn block_comment_len(input: &str) -> Result<usize, ParseErr> {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < input.len() {
+        if input[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if input[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Ok(i);
+            }
+        } else {
+            i += input[i..].chars().next().unwrap().len_utf8();
+        }
+    }
+    return Err(ParseErr::Unexpected(
+        "*/".to_string(),
+        "nothing".to_string(),
+        0,
+    ));
+}
+
+fn parse_char(c: char) -> impl Fn(String) -> ParseResult {
+    return move |input: String| {
+        if input.is_empty() {
+            return ParseResult::Err(ParseErr::Unexpected(
+                c.to_string(),
+                "nothing".to_string(),
+                0,
+            ));
+        }
+        if input.chars().next().unwrap() == c {
+            return ParseResult::Ok((input[c.len_utf8()..].to_string(), ParseObj::Matched(c)));
+        }
+        return ParseResult::Err(ParseErr::Unexpected(
+            c.to_string(),
+            input.chars().next().unwrap().to_string(),
+            0,
+        ));
+    };
+}
+
+fn one_or_more(parser: impl Fn(String) -> ParseResult) -> impl Fn(String) -> ParseResult {
+    return move |mut input: String| {
+        let mut result = Vec::new();
+
+        // we should first try to get one, if can't it's a parse error
+        match parser(input.clone()) {
+            Ok((remains, parsed)) => {
+                input = remains;
+                result.push(parsed);
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        }
+        while let Ok((remains, parsed)) = parser(input.clone()) {
+            input = remains;
+            result.push(parsed);
+        }
+        return Ok((input.clone(), ParseObj::List(result)));
+    };
+}
+fn parse_chars(chars: &str) -> impl Fn(String) -> ParseResult {
+    let parsers = chars.chars().map(parse_char).collect();
+    return any_of(parsers);
+}
+
+fn any_of(parsers: Vec<impl Fn(String) -> ParseResult>) -> impl Fn(String) -> ParseResult {
+    return move |input: String| {
+        for parser in parsers.iter() {
+            match parser(input.clone()) {
+                Ok((remaining, parsed)) => return Ok((remaining, parsed)),
+                Err(_) => continue,
+            }
+        }
+        return Err(ParseErr::Unexpected("".to_string(), "".to_string(), 0));
+    };
+}
+
+// 把 one_or_more 得到的 List(Matched...) 拼回字符串
+fn collect_chars(obj: ParseObj) -> Result<String, ParseErr> {
+    match obj {
+        ParseObj::List(chars_parse_objects) => {
+            let mut text = String::new();
+            for po in chars_parse_objects {
+                match po {
+                    ParseObj::Matched(c) => text.push(c),
+                    _ => {
+                        return Err(ParseErr::Unexpected(
+                            "a char".to_string(),
+                            format!("{:?}", po),
+                            0,
+                        ))
+                    }
+                }
+            }
+            return Ok(text);
+        }
+        // 匹配只有一个字符作为标志符的情况
+        _ => {
+            return Err(ParseErr::Unexpected(
+                "list of chars".to_string(),
+                format!("{:?}", obj),
+                0,
+            ))
+        }
+    }
+}
+
+fn optional(parser: impl Fn(String) -> ParseResult) -> impl Fn(String) -> ParseResult {
+    return move |input: String| match parser(input.clone()) {
+        Ok((remains, parsed)) => Ok((remains, parsed)),
+        Err(_) => Ok((input, ParseObj::Empty)),
+    };
+}
+
+// 保留字，不能用作标识符
+const KEYWORDS: [&str; 19] = [
+    "true",
+    "false",
+    "if",
+    "else",
+    "for",
+    "in",
+    "while",
+    "loop",
+    "break",
+    "continue",
+    "return",
+    "fn",
+    "struct",
+    "enum",
+    "interface",
+    "impl",
+    "match",
+    "let",
+    "var",
+];
+
+// 匹配一个满足 pred 的字符，expected 用于错误信息
+fn parse_char_if(expected: &'static str, pred: fn(char) -> bool) -> impl Fn(String) -> ParseResult {
+    return move |input: String| match input.chars().next() {
+        Some(c) if pred(c) => Ok((input[c.len_utf8()..].to_string(), ParseObj::Matched(c))),
+        _ => Err(ParseErr::Unexpected(
+            expected.to_string(),
+            next_found(&input),
+            0,
+        )),
+    };
+}
+
+// 单词: (XID_Start | _) XID_Continue*，包括 [A-Za-z_][A-Za-z0-9_]* 和非 ASCII 的名字
+// 保留字得到 Keyword，其它是 Ident
+fn word(input: String) -> ParseResult {
+    let (remains, first) = parse_char_if("identifier", is_ident_start)(input.clone())?;
+    let (remains, rest) = zero_or_more(parse_char_if("identifier", is_ident_char))(remains)?;
+    let mut name = collect_chars(ParseObj::List(vec![first]))?;
+    name.push_str(&collect_chars(rest)?);
+    if KEYWORDS.contains(&name.as_str()) {
+        return Ok((remains, ParseObj::Keyword(name)));
+    }
+    return Ok((remains, ParseObj::Ident(name)));
+}
+
+fn is_ident_start(c: char) -> bool {
+    return c == '_' || unicode_ident::is_xid_start(c);
+}
+
+fn is_ident_char(c: char) -> bool {
+    return unicode_ident::is_xid_continue(c);
+}
+
+// 用于错误信息里的 found: 下一个字符，没有了就是 nothing
+fn next_found(input: &str) -> String {
+    match input.chars().next() {
+        Some(c) => c.to_string(),
+        None => "nothing".to_string(),
+    }
+}
+
+// 十进制数字串，允许用 _ 分隔（但不能以 _ 开头），返回去掉 _ 后的数字
+fn decimal_digits(input: String) -> Result<(String, String), ParseErr> {
+    let (remains, _) = parse_chars("0123456789")(input.clone())?;
+    let (remains, _) = zero_or_more(parse_chars("0123456789_"))(remains)?;
+    let digits = input[..input.len() - remains.len()].replace('_', "");
+    return Ok((remains, digits));
+}
+
+// 数字字面量:
+//   整数 [+-]digits[i|u]，也可以是 0x 0o 0b 开头的十六/八/二进制
+//   浮点 [+-][digits][.digits][(e|E)[+-]digits]，如 1.5 .5 1e10 2.5E-3 1_000.25
+// 带 u 后缀的是 Uint，其它（无后缀、i 后缀、带符号）都是 Int，Uint 不允许带符号
+// 错误里的 location 是相对于传入 input 的偏移
+pub fn number(input: String) -> ParseResult {
+    let offset = |remains: &String| (input.len() - remains.len()) as u64;
+    let sign_parser = || optional(any_of(vec![parse_char('-'), parse_char('+')]));
+
+    let (remains, sign) = sign_parser()(input.clone())?;
+    for (prefix, radix, name) in [
+        ("0x", 16, "hexadecimal"),
+        ("0o", 8, "octal"),
+        ("0b", 2, "binary"),
+    ] {
+        if remains.starts_with(prefix) {
+            return radix_integer(&input, remains[2..].to_string(), sign, radix, name);
+        }
+    }
+    let (mut remains, int_part) = match decimal_digits(remains.clone()) {
+        Ok((r, digits)) => (r, Some(digits)),
+        Err(_) => (remains, None),
+    };
+
+    let mut frac_part = None;
+    if let Ok((r, _)) = parse_char('.')(remains.clone()) {
+        match decimal_digits(r.clone()) {
+            Ok((r, digits)) => {
+                remains = r;
+                frac_part = Some(digits);
+            }
+            // 1.e 1..2 1. 这类都不是合法的浮点数
+            Err(_) => {
+                return Err(ParseErr::Unexpected(
+                    "digit after `.`".to_string(),
+                    next_found(&r),
+                    offset(&r),
+                ))
+            }
+        }
+    }
+    if int_part.is_none() && frac_part.is_none() {
+        return Err(ParseErr::Unexpected(
+            "digit".to_string(),
+            next_found(&remains),
+            offset(&remains),
+        ));
+    }
+
+    let mut exp_part = None;
+    if let Ok((r, _)) = any_of(vec![parse_char('e'), parse_char('E')])(remains.clone()) {
+        let (r, exp_sign) = sign_parser()(r)?;
+        match decimal_digits(r.clone()) {
+            Ok((r, digits)) => {
+                remains = r;
+                exp_part = Some((exp_sign, digits));
+            }
+            Err(_) => {
+                return Err(ParseErr::Unexpected(
+                    "exponent digits".to_string(),
+                    next_found(&r),
+                    offset(&r),
+                ))
+            }
+        }
+    }
+
+    if frac_part.is_some() || exp_part.is_some() {
+        let literal = input[..input.len() - remains.len()].to_string();
+        // 1.5.2 不行，1.5..2 是区间，1.5.floor() 是方法调用，. 和 .. 都留给后面
+        if let Some(c) = remains.chars().next() {
+            if is_ident_char(c)
+                || (c == '.' && remains[1..].starts_with(|c: char| c.is_ascii_digit()))
+            {
+                return Err(ParseErr::Unexpected(
+                    "end of float literal".to_string(),
+                    c.to_string(),
+                    offset(&remains),
+                ));
+            }
+        }
+
+        let mut text = String::new();
+        if sign == ParseObj::Matched('-') {
+            text.push('-');
+        }
+        text.push_str(&int_part.unwrap_or("0".to_string()));
+        text.push('.');
+        text.push_str(&frac_part.unwrap_or("0".to_string()));
+        if let Some((exp_sign, digits)) = exp_part {
+            text.push('e');
+            if exp_sign == ParseObj::Matched('-') {
+                text.push('-');
+            }
+            text.push_str(&digits);
+        }
+        return match text.parse::<f64>() {
+            Ok(f) if f.is_finite() => Ok((remains, ParseObj::Float(f))),
+            _ => Err(ParseErr::Unexpected(
+                format!("float literal in range {:e}..={:e}", f64::MIN, f64::MAX),
+                literal,
+                0,
+            )),
+        };
+    }
+
+    return integer_value(&input, remains, sign, int_part.unwrap(), 10);
+}
+
+// 0x 0o 0b 前缀后面的数字部分，遇到 i/u 后缀或者非标识符字符为止
+// 不合法的数字（比如二进制里的 2）直接报出来，而不是当成别的东西
+fn radix_integer(input: &str, body: String, sign: ParseObj, radix: u32, name: &str) -> ParseResult {
+    let offset = (input.len() - body.len()) as u64;
+    let end = body
+        .find(|c: char| !is_ident_char(c) || c == 'i' || c == 'u')
+        .unwrap_or(body.len());
+    for (i, c) in body[..end].char_indices() {
+        if c != '_' && !c.is_digit(radix) {
+            return Err(ParseErr::Unexpected(
+                format!("{} digit", name),
+                c.to_string(),
+                offset + i as u64,
+            ));
+        }
+    }
+    let digits = body[..end].replace('_', "");
+    if digits.is_empty() {
+        return Err(ParseErr::Unexpected(
+            format!("{} digit", name),
+            next_found(&body[end..]),
+            offset + end as u64,
+        ));
+    }
+    return integer_value(input, body[end..].to_string(), sign, digits, radix);
+}
+
+// 整数的后缀和范围检查，input 是整个字面量开始的位置
+fn integer_value(
+    input: &str,
+    remains: String,
+    sign: ParseObj,
+    digits: String,
+    radix: u32,
+) -> ParseResult {
+    let (remains, suffix) = optional(any_of(vec![parse_char('i'), parse_char('u')]))(remains)?;
+    let literal = input[..input.len() - remains.len()].to_string();
+
+    if let Some(c) = remains.chars().next() {
+        if is_ident_char(c) {
+            return Err(ParseErr::Unexpected(
+                "integer suffix `i` or `u`".to_string(),
+                c.to_string(),
+                literal.len() as u64,
+            ));
+        }
+    }
+
+    let negative = sign == ParseObj::Matched('-');
+    if suffix == ParseObj::Matched('u') {
+        if sign != ParseObj::Empty {
+            return Err(ParseErr::Unexpected(
+                "unsigned literal without sign".to_string(),
+                literal,
+                0,
+            ));
+        }
+        return match usize::from_str_radix(&digits, radix) {
+            Ok(n) => Ok((remains, ParseObj::Uint(n))),
+            Err(_) => Err(ParseErr::Unexpected(
+                format!("integer literal in range 0..={}", usize::MAX),
+                literal,
+                0,
+            )),
+        };
+    }
+
+    let signed_digits = if negative {
+        format!("-{}", digits)
+    } else {
+        digits
+    };
+    return match isize::from_str_radix(&signed_digits, radix) {
+        Ok(n) => Ok((remains, ParseObj::Int(n))),
+        Err(_) => Err(ParseErr::Unexpected(
+            format!("integer literal in range {}..={}", isize::MIN, isize::MAX),
+            literal,
+            0,
+        )),
+    };
+}
+
+// 把子解析器里相对的 location 挪到调用方的坐标下
+pub fn offset_err(err: ParseErr, offset: u64) -> ParseErr {
+    match err {
+        ParseErr::Unexpected(expected, found, location) => {
+            ParseErr::Unexpected(expected, found, location + offset)
+        }
+        _ => err,
+    }
+}
+
+// 转义序列，input 以 `\` 开头: \n \t \r \0 \\ \" \' \u{1F600}
+// 出错时 location 指向 `\`
+fn escape_sequence(input: String) -> Result<(String, char), ParseErr> {
+    let (remains, _) = parse_char('\\')(input)?;
+    let c = match remains.chars().next() {
+        Some(c) => c,
+        None => {
+            return Err(ParseErr::Unexpected(
+                "escape sequence".to_string(),
+                "nothing".to_string(),
+                0,
+            ))
+        }
+    };
+    let rest = remains[c.len_utf8()..].to_string();
+    return match c {
+        'n' => Ok((rest, '\n')),
+        't' => Ok((rest, '\t')),
+        'r' => Ok((rest, '\r')),
+        '0' => Ok((rest, '\0')),
+        '\\' => Ok((rest, '\\')),
+        '"' => Ok((rest, '"')),
+        '\'' => Ok((rest, '\'')),
+        'u' => unicode_escape(rest),
+        _ => Err(ParseErr::Unexpected(
+            "escape sequence".to_string(),
+            format!("\\{}", c),
+            0,
+        )),
+    };
+}
+
+// \u 后面的 {hex}，最多 6 位，必须是合法的 unicode 标量值
+fn unicode_escape(input: String) -> Result<(String, char), ParseErr> {
+    let invalid =
+        |found: String| ParseErr::Unexpected("unicode escape `\\u{...}`".to_string(), found, 0);
+    let (remains, _) = parse_char('{')(input.clone()).map_err(|_| invalid(next_found(&input)))?;
+    let (remains, hex) = one_or_more(parse_chars("0123456789abcdefABCDEF"))(remains.clone())
+        .map_err(|_| invalid(next_found(&remains)))?;
+    let hex = collect_chars(hex)?;
+    let (remains, _) =
+        parse_char('}')(remains.clone()).map_err(|_| invalid(next_found(&remains)))?;
+    if hex.len() > 6 {
+        return Err(invalid(format!("\\u{{{}}}", hex)));
+    }
+    return match char::from_u32(u32::from_str_radix(&hex, 16).unwrap()) {
+        Some(c) => Ok((remains, c)),
+        None => Err(invalid(format!("\\u{{{}}}", hex))),
+    };
+}
+
+// 字符串字面量 "..."，支持转义；r"..." r#"..."# 是不处理转义的原始字符串
+pub fn string(input: String) -> ParseResult {
+    if input.starts_with('r') {
+        return raw_string(input);
+    }
+    parse_char('"')(input.clone())?;
+    let mut value = String::new();
+    // 用下标往前走，转义序列只把它自己那一段交给 escape_sequence
+    let mut i = 1;
+    loop {
+        let rest = &input[i..];
+        match rest.chars().next() {
+            None => {
+                return Err(ParseErr::Unexpected(
+                    "closing `\"`".to_string(),
+                    "nothing".to_string(),
+                    0,
+                ))
+            }
+            Some('"') => return Ok((input[i + 1..].to_string(), ParseObj::Str(value))),
+            Some('\\') => {
+                let escape = &rest[..escape_end(rest)];
+                let (r, c) =
+                    escape_sequence(escape.to_string()).map_err(|err| offset_err(err, i as u64))?;
+                i += escape.len() - r.len();
+                value.push(c);
+            }
+            Some(c) => {
+                value.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+}
+
+fn raw_string(input: String) -> ParseResult {
+    let (remains, _) = parse_char('r')(input.clone())?;
+    let (remains, hashes) = zero_or_more(parse_char('#'))(remains)?;
+    let (remains, _) = parse_char('"')(remains)?;
+    let mut terminator = "\"".to_string();
+    if let ParseObj::List(hashes) = hashes {
+        terminator.push_str(&"#".repeat(hashes.len()));
+    }
+    return match remains.find(&terminator) {
+        Some(end) => Ok((
+            remains[end + terminator.len()..].to_string(),
+            ParseObj::Str(remains[..end].to_string()),
+        )),
+        None => Err(ParseErr::Unexpected(
+            format!("closing `{}`", terminator),
+            "nothing".to_string(),
+            0,
+        )),
+    };
+}
+
+// char 字面量 'a' '\n' '\u{263A}'，引号里必须正好一个字符
+pub fn char_literal(input: String) -> ParseResult {
+    let (remains, _) = parse_char('\'')(input.clone())?;
+    let offset = (input.len() - remains.len()) as u64;
+    let (remains, c) = match remains.chars().next() {
+        Some('\\') => escape_sequence(remains).map_err(|err| offset_err(err, offset))?,
+        Some('\'') => {
+            return Err(ParseErr::Unexpected(
+                "a character".to_string(),
+                "''".to_string(),
+                0,
+            ))
+        }
+        Some(c) => (remains[c.len_utf8()..].to_string(), c),
+        None => {
+            return Err(ParseErr::Unexpected(
+                "a character".to_string(),
+                "nothing".to_string(),
+                offset,
+            ))
+        }
+    };
+    let offset = (input.len() - remains.len()) as u64;
+    return match parse_char('\'')(remains.clone()) {
+        Ok((remains, _)) => Ok((remains, ParseObj::Char(c))),
+        Err(_) => Err(ParseErr::Unexpected(
+            "closing `'`".to_string(),
+            next_found(&remains),
+            offset,
+        )),
+    };
+}
+
+#[test]
+fn test_parse_number_suffix() {
+    assert_eq!(
+        number("10u".to_string()),
+        Ok(("".to_string(), ParseObj::Uint(10)))
+    );
+    assert_eq!(
+        number("-3i".to_string()),
+        Ok(("".to_string(), ParseObj::Int(-3)))
+    );
+    assert_eq!(
+        number("42".to_string()),
+        Ok(("".to_string(), ParseObj::Int(42)))
+    );
+    assert_eq!(
+        number("+7".to_string()),
+        Ok(("".to_string(), ParseObj::Int(7)))
+    );
+    assert!(number("-3u".to_string()).is_err());
+    assert!(number("3x".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_number_overflow() {
+    assert_eq!(
+        number(isize::MIN.to_string()),
+        Ok(("".to_string(), ParseObj::Int(isize::MIN)))
+    );
+    let res = number("99999999999999999999".to_string());
+    assert!(
+        matches!(res, Err(ParseErr::Unexpected(_, found, 0)) if found == "99999999999999999999")
+    );
+    assert!(number(format!("{}0u", usize::MAX)).is_err());
+}
+
+#[test]
+fn test_parse_float() {
+    let cases = vec![
+        ("1.5", 1.5),
+        (".5", 0.5),
+        ("1e10", 1e10),
+        ("2.5E-3", 2.5e-3),
+        ("-1_000.25", -1000.25),
+    ];
+    for (src, expected) in cases {
+        assert_eq!(
+            number(src.to_string()),
+            Ok(("".to_string(), ParseObj::Float(expected)))
+        );
+    }
+}
+
+#[test]
+fn test_parse_float_malformed() {
+    assert_eq!(
+        number("1.e".to_string()),
+        Err(ParseErr::Unexpected(
+            "digit after `.`".to_string(),
+            "e".to_string(),
+            2
+        ))
+    );
+    assert_eq!(
+        number("1..2".to_string()),
+        Err(ParseErr::Unexpected(
+            "digit after `.`".to_string(),
+            ".".to_string(),
+            2
+        ))
+    );
+    assert_eq!(
+        number("2.5e+".to_string()),
+        Err(ParseErr::Unexpected(
+            "exponent digits".to_string(),
+            "nothing".to_string(),
+            5
+        ))
+    );
+    assert!(number("1.5u".to_string()).is_err());
+    assert!(number("1e999".to_string()).is_err());
+    // 从源码进来也一样，不会被当成字段 e
+    assert_eq!(
+        tokenize("a = 1.e;").map(|_| ()),
+        Err(ParseErr::Unexpected(
+            "digit after `.`".to_string(),
+            "e".to_string(),
+            6
+        ))
+    );
+    assert!(tokenize("1.e5").is_err());
+    assert!(tokenize("1.E+3").is_err());
+    assert_eq!(tokenize("1.exp()").unwrap()[2].text, "exp");
+}
+
+#[test]
+fn test_parse_string() {
+    assert_eq!(
+        string(r#""a\tb\n\\ \"q\" \u{1F600}" rest"#.to_string()),
+        Ok((
+            " rest".to_string(),
+            ParseObj::Str("a\tb\n\\ \"q\" 😀".to_string())
+        ))
+    );
+    assert_eq!(
+        string(r#"r"C:\path""#.to_string()),
+        Ok(("".to_string(), ParseObj::Str(r"C:\path".to_string())))
+    );
+    assert_eq!(
+        string(r###"r#"say "hi""#"###.to_string()),
+        Ok(("".to_string(), ParseObj::Str(r#"say "hi""#.to_string())))
+    );
+}
+
+#[test]
+fn test_parse_string_errors() {
+    assert_eq!(
+        string(r#""ab\qc""#.to_string()),
+        Err(ParseErr::Unexpected(
+            "escape sequence".to_string(),
+            r"\q".to_string(),
+            3
+        ))
+    );
+    assert_eq!(
+        string(r#""ok \u{D800}""#.to_string()),
+        Err(ParseErr::Unexpected(
+            r"unicode escape `\u{...}`".to_string(),
+            r"\u{D800}".to_string(),
+            4
+        ))
+    );
+    assert!(matches!(
+        string(r#""never closed"#.to_string()),
+        Err(ParseErr::Unexpected(_, _, 0))
+    ));
+    assert!(string(r##"r#"no hash""##.to_string()).is_err());
+}
+
+#[test]
+fn test_parse_char_literal() {
+    assert_eq!(
+        char_literal("'a'".to_string()),
+        Ok(("".to_string(), ParseObj::Char('a')))
+    );
+    assert_eq!(
+        char_literal(r"'\n'".to_string()),
+        Ok(("".to_string(), ParseObj::Char('\n')))
+    );
+    assert_eq!(
+        char_literal(r"'\u{263A}'".to_string()),
+        Ok(("".to_string(), ParseObj::Char('☺')))
+    );
+    assert_eq!(
+        char_literal("'ab'".to_string()),
+        Err(ParseErr::Unexpected(
+            "closing `'`".to_string(),
+            "b".to_string(),
+            2
+        ))
+    );
+    assert!(char_literal("''".to_string()).is_err());
+    assert!(char_literal("'a".to_string()).is_err());
+}
+
+#[test]
+fn test_matched_chars_are_not_char_values() {
+    let (_, parsed) = one_or_more(parse_char('x'))("xx".to_string()).unwrap();
+    assert_eq!(
+        parsed,
+        ParseObj::List(vec![ParseObj::Matched('x'), ParseObj::Matched('x')])
+    );
+}
+
+#[test]
+fn test_parse_radix_literals() {
+    assert_eq!(
+        number("0xFF".to_string()),
+        Ok(("".to_string(), ParseObj::Int(255)))
+    );
+    assert_eq!(
+        number("0o755u".to_string()),
+        Ok(("".to_string(), ParseObj::Uint(0o755)))
+    );
+    assert_eq!(
+        number("-0b1010_0001".to_string()),
+        Ok(("".to_string(), ParseObj::Int(-0b1010_0001)))
+    );
+    assert_eq!(
+        number("0xFFFF_FFFF_FFFF_FFFFu".to_string()),
+        Ok(("".to_string(), ParseObj::Uint(usize::MAX)))
+    );
+    assert!(number("0xFFFF_FFFF_FFFF_FFFF".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_radix_invalid_digit() {
+    assert_eq!(
+        number("0b1012".to_string()),
+        Err(ParseErr::Unexpected(
+            "binary digit".to_string(),
+            "2".to_string(),
+            5
+        ))
+    );
+    assert_eq!(
+        number("0o78".to_string()),
+        Err(ParseErr::Unexpected(
+            "octal digit".to_string(),
+            "8".to_string(),
+            3
+        ))
+    );
+    assert_eq!(
+        number("0x".to_string()),
+        Err(ParseErr::Unexpected(
+            "hexadecimal digit".to_string(),
+            "nothing".to_string(),
+            2
+        ))
+    );
+}
+
+#[test]
+fn test_whitespace_comments() {
+    let src = "  // line\r\n\t/* outer /* nested */ still outer */\r\nx";
+    let tokens = tokenize(src).unwrap();
+    assert_eq!(tokens[0].kind, TokenKind::Ident);
+    assert_eq!(tokens[0].span.start, src.len() - 1);
+    assert!(tokenize("/* open /* nested */").is_err());
+}
+
+#[test]
+fn test_tokenize() {
+    let tokens = tokenize("/// doc\nx1: if = -2.5 // done").unwrap();
+    let kinds: Vec<(TokenKind, &str, usize, usize)> = tokens
+        .iter()
+        .map(|t| (t.kind, t.text.as_str(), t.span.start, t.span.end))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (TokenKind::DocComment, " doc", 0, 7),
+            (TokenKind::Ident, "x1", 8, 10),
+            (TokenKind::Punct, ":", 10, 11),
+            (TokenKind::Keyword, "if", 12, 14),
+            (TokenKind::Op, "=", 15, 16),
+            (TokenKind::Op, "-", 17, 18),
+            (TokenKind::Float, "2.5", 18, 21),
+            (TokenKind::Eof, "", 29, 29),
+        ]
+    );
+    let ops: Vec<String> = tokenize("a<=b==!c&&d")
+        .unwrap()
+        .into_iter()
+        .filter(|t| t.kind == TokenKind::Op)
+        .map(|t| t.text)
+        .collect();
+    assert_eq!(ops, vec!["<=", "==", "!", "&&"]);
+    let ops: Vec<String> = tokenize("a+=b-=-c%=d")
+        .unwrap()
+        .into_iter()
+        .filter(|t| t.kind == TokenKind::Op)
+        .map(|t| t.text)
+        .collect();
+    assert_eq!(ops, vec!["+=", "-=", "-", "%="]);
+}
+
+#[test]
+fn test_tokenize_errors_are_located() {
+    assert_eq!(
+        tokenize("a = 0b102"),
+        Err(ParseErr::Unexpected(
+            "binary digit".to_string(),
+            "2".to_string(),
+            8
+        ))
+    );
+    assert_eq!(
+        tokenize("a = 1 /* open"),
+        Err(ParseErr::Unexpected(
+            "*/".to_string(),
+            "nothing".to_string(),
+            6
+        ))
+    );
+    assert_eq!(
+        tokenize("a = $"),
+        Err(ParseErr::Unexpected(
+            "token".to_string(),
+            "$".to_string(),
+            4
+        ))
+    );
+}
+
+#[test]
+fn test_tokenize_ranges_and_labels() {
+    let tokens: Vec<(TokenKind, String)> = tokenize("'outer: 0..10 1..=n 'x' 0.5..")
+        .unwrap()
+        .into_iter()
+        .map(|t| (t.kind, t.text))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenKind::Label, "'outer".to_string()),
+            (TokenKind::Punct, ":".to_string()),
+            (TokenKind::Int, "0".to_string()),
+            (TokenKind::Op, "..".to_string()),
+            (TokenKind::Int, "10".to_string()),
+            (TokenKind::Int, "1".to_string()),
+            (TokenKind::Op, "..=".to_string()),
+            (TokenKind::Ident, "n".to_string()),
+            (TokenKind::Char, "'x'".to_string()),
+            (TokenKind::Float, "0.5".to_string()),
+            (TokenKind::Op, "..".to_string()),
+            (TokenKind::Eof, "".to_string()),
+        ]
+    );
+    assert!(tokenize("'ab'").is_err());
+    assert_eq!(tokenize("break 'outer").unwrap()[1].kind, TokenKind::Label);
+    // 不在标签的位置上，还是没闭合的字符字面量
+    assert_eq!(
+        tokenize("let x = 'a;").map(|_| ()),
+        Err(ParseErr::Unexpected(
+            "closing `'`".to_string(),
+            ";".to_string(),
+            10
+        ))
+    );
+}
@@ -1,14 +1,13 @@
-#![allow(dead_code)]
-/*TODO
-    - for
-        - c syntax
-        - foreach
-        - while syntax
-    - interface
-    - operator expressions
-*/
+#![allow(dead_code, clippy::needless_return)]
+mod lexer;
+
+use lexer::{tokenize, Token, TokenKind};
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum ParseObj {
+    // parse_char 等组合子内部匹配到的字符，只在解析过程中出现，不是程序里的值
+    Matched(char),
+    // 语言里的 char 字面量 'a'
     Char(char),
     Uint(usize),
     Int(isize),
@@ -19,18 +18,132 @@ pub enum ParseObj {
     Bool(bool),
     List(Vec<ParseObj>),
     // Box用于在堆上分配空间并存储值，这在你需要存储大型数据或具有递归数据类型的时候特别有用。
-    Decl(String, Box<Option<ParseObj>>, Box<ParseObj>),
-    FnCall(String, Vec<ParseObj>),
-    Struct(Vec<(ParseObj, ParseObj)>),
-    Fn(Vec<(ParseObj, ParseObj)>, Box<ParseObj>, Box<ParseObj>),
-    Array(Box<Option<ParseObj>>, Box<ParseObj>),
+    // let 声明 (名字, 类型, 值)，类型没写就是 None，声明之后不能再赋值
+    Decl(String, Box<Option<Type>>, Box<ParseObj>),
+    // var 声明，和 Decl 一样，但之后可以重新赋值
+    Var(String, Box<Option<Type>>, Box<ParseObj>),
+    // 给已有的变量、字段或下标赋值 x = 5 / p.x = 1 / a[i] = 0，(位置, 值)
+    Assign(Box<ParseObj>, Box<ParseObj>),
+    // 复合赋值 x += 1，(运算符 + - * / %, 位置, 值)
+    CompoundAssign(String, Box<ParseObj>, Box<ParseObj>),
+    // 调用 (被调用的表达式, 参数)，被调用的可以是任意表达式: make_adder(1)(2)
+    FnCall(Box<ParseObj>, Vec<ParseObj>),
+    // 字段访问 p.x，(对象, 字段名)
+    Field(Box<ParseObj>, String),
+    // 下标 list[3]，(被索引的表达式, 下标)
+    Index(Box<ParseObj>, Box<ParseObj>),
+    // 方法调用 s.len()，(接收者, 方法名, 参数)
+    MethodCall(Box<ParseObj>, String, Vec<ParseObj>),
+    // struct 类型 struct { x: float, y: float }，(字段名, 类型)
+    Struct(Vec<(ParseObj, Type)>),
+    // struct 字面量 Point { x: 1.0, y }，(类型名, [(字段名, 值)])，简写的 y 就是 (y, y)
+    // 类型名也可以是 enum 成员的 Path: Shape::Rect { w: 1.0, h: 2.0 }
+    StructLit(Box<ParseObj>, Vec<(ParseObj, ParseObj)>),
+    // enum 类型 enum { Circle(float), Rect { w: float, h: float }, Empty }
+    Enum(Vec<Variant>),
+    // enum 成员 Shape::Circle，(enum 名, 成员名)
+    // 构造带值的成员就是调用它 Shape::Circle(1.0) 或者写成 struct 字面量 Shape::Rect { .. }
+    Path(String, String),
+    // 函数 ([(参数名, 类型)], 返回类型, body)，返回类型没写就是 None
+    Fn(Vec<(ParseObj, Type)>, Box<Option<Type>>, Box<ParseObj>),
+    // interface { area(): float, scale(by: float) }，(方法名, 方法签名)
+    // 签名就是没有 body 的 Fn，body 是 Empty
+    Interface(Vec<(ParseObj, ParseObj)>),
+    // impl Shape for Circle { fn area(): float { ... } }，(interface, 实现它的类型, 方法)
+    // 每个方法都是 fn name() {} 那样的 Decl
+    Impl(Box<ParseObj>, Box<Type>, Vec<ParseObj>),
+    // 数组字面量 [1, 2, 3]
+    ArrayLit(Vec<ParseObj>),
+    // 重复的数组字面量 [0; 16]，(值, 个数)
+    ArrayRepeat(Box<ParseObj>, Box<ParseObj>),
     Stmt(Box<ParseObj>),
     Block(Vec<ParseObj>),
-    If(Box<ParseObj>, Box<ParseObj>),
+    // if (条件, 满足时的块, else 分支)，else 分支是块或者下一个 If (else if)
+    If(Box<ParseObj>, Box<ParseObj>, Box<Option<ParseObj>>),
+    // for init; cond; step { body }，没写的部分是 Empty
     ForC(Box<ParseObj>, Box<ParseObj>, Box<ParseObj>, Box<ParseObj>),
+    // for x in xs { body }，(变量, 被遍历的数组或区间, body)
+    ForEach(Box<ParseObj>, Box<ParseObj>, Box<ParseObj>),
+    // while cond { body }
+    While(Box<ParseObj>, Box<ParseObj>),
+    // loop { body }
+    Loop(Box<ParseObj>),
+    // 带标签的循环 'outer: loop { }，标签名不带 '
+    Labeled(String, Box<ParseObj>),
+    // match x { 模式 => 值, ... }，(被匹配的表达式, [Arm])
+    Match(Box<ParseObj>, Vec<ParseObj>),
+    // match 的一个分支 (模式, if 守卫, 值)
+    Arm(Box<Pattern>, Box<Option<ParseObj>>, Box<ParseObj>),
+    // return 可以不带值
+    Return(Box<Option<ParseObj>>),
+    // break / continue，可以带标签: break 'outer，break 还可以带一个值: break 'outer x
+    // 最后是关键字在源码里的位置，写在循环外面时用来报错
+    Break(Option<String>, Box<Option<ParseObj>>, u64),
+    Continue(Option<String>, u64),
+    // 二元运算 (运算符, 左, 右)
+    Binary(String, Box<ParseObj>, Box<ParseObj>),
+    // 一元运算 (运算符, 操作数)，- 和 !
+    Unary(String, Box<ParseObj>),
+    // lexer 跳过空白时遇到的 `///` 文档注释
+    DocComment(String),
+    // 带文档注释的 Decl / Fn
+    Doc(Vec<String>, Box<ParseObj>),
+    // 整个源文件，顶层的声明和 impl 按顺序放在一起
+    Program(Vec<ParseObj>),
     Empty,
 }
 
+// 类型标注，只出现在类型的位置上: 声明的 : 后面、参数、返回类型、struct 字段
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    // int, Point
+    Named(String),
+    // 泛型 List<int>, Map<string, []int>，(名字, 类型参数)
+    Generic(String, Vec<Type>),
+    // 数组类型 (长度, 元素类型): []int 没有长度，[4]float 长度是 4
+    Array(Box<Option<ParseObj>>, Box<Type>),
+    // 函数类型 fn(int, int): int，(参数类型, 返回类型)
+    Fn(Vec<Type>, Box<Option<Type>>),
+    // 可以没有值的类型 ?int
+    Optional(Box<Type>),
+    // 直接写在类型位置上的 struct { ... } / interface { ... }，和表达式里的一样
+    Struct(Vec<(ParseObj, Type)>),
+    Interface(Vec<(ParseObj, ParseObj)>),
+}
+
+// enum 的一个成员
+#[derive(Clone, Debug, PartialEq)]
+pub enum Variant {
+    // Empty
+    Unit(String),
+    // Circle(float)，(名字, 各个值的类型)
+    Tuple(String, Vec<Type>),
+    // Rect { w: float, h: float }，(名字, [(字段名, 类型)])
+    Struct(String, Vec<(ParseObj, Type)>),
+}
+
+// match 分支里的模式
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    // _，什么都能匹配
+    Wildcard,
+    // 字面量 1, -2.5, "a", 'c', true
+    Literal(Box<ParseObj>),
+    // 名字，什么都能匹配，并把值绑定到这个名字上
+    Binding(String),
+    // struct 解构 Point { x: 0, y, .. }，(类型名, [(字段名, 模式)], 有没有 ..)
+    // 只写字段名 y 是 y: y 的简写，类型名是 Ident，解构 enum 成员时是 Path: Shape::Rect { w, .. }
+    Struct(Box<ParseObj>, Vec<(String, Pattern)>, bool),
+    // enum 成员 Shape::Circle(r) / Shape::Empty，(enum 名, 成员名, 括号里的模式)
+    Variant(String, String, Vec<Pattern>),
+    // 数组 [first, .., last]
+    Array(Vec<Pattern>),
+    // 数组模式里的 ..，匹配剩下的任意多个元素
+    Rest,
+    // 区间 1..10 / 'a'..='z'，(下界, 上界, 包不包含上界)
+    Range(Box<ParseObj>, Box<ParseObj>, bool),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ParseErr {
     // unexpected (expected, found, location)
@@ -49,7 +162,6 @@ impl std::fmt::Display for ParseErr {
         match self {
             Self::Unexpected(_, _, _) => f.write_fmt(format_args!("{:?}", self)),
             Self::Unknown(msg) => f.write_fmt(format_args!("{}", msg)),
-            _ => unreachable!(),
         }
     }
 }
@@ -59,230 +171,2787 @@ impl std::fmt::Display for ParseErr {
 impl std::error::Error for ParseErr {}
 
 type ParseResult = Result<(String, ParseObj), ParseErr>;
-fn zero_or_more(parser: impl Fn(String) -> ParseResult) -> impl Fn(String) -> ParseResult {
-    return move |mut input: String| {
-        let mut result = Vec::new();
-        while let Ok((remains, parsed)) = parser(input.clone()) {
-            input = remains;
-            result.push(parsed);
+
+// 语法规则都在 token 上工作，剩下没用到的 token 跟着结果一起返回
+type TokenResult<'a> = Result<(&'a [Token], ParseObj), ParseErr>;
+type Rule = for<'a> fn(&'a [Token]) -> TokenResult<'a>;
+
+// 字符串入口: 先交给 lexer 切成 token，再用 token 上的规则解析
+// 返回的 remains 是规则没有用到的那部分源码
+fn parse_str(rule: Rule, input: String) -> ParseResult {
+    let tokens = drop_stray_doc_comments(tokenize(&input)?);
+    let (remains, obj) = rule(&tokens)?;
+    check_jumps(&obj)?;
+    return Ok((input[remains[0].span.start..].to_string(), obj));
+}
+
+// 解析整个源文件，必须一直解析到文件结尾，后面剩下任何东西都是错误
+pub fn parse_program(input: String) -> Result<ParseObj, ParseErr> {
+    let tokens = drop_stray_doc_comments(tokenize(&input)?);
+    let (_, program) = program(&tokens)?;
+    check_jumps(&program)?;
+    return Ok(program);
+}
+
+// 文档注释只留给紧跟在后面的声明和 impl，其它地方的（struct 字段前面、文件末尾……）当普通注释丢掉
+fn drop_stray_doc_comments(tokens: Vec<Token>) -> Vec<Token> {
+    let mut kept = Vec::with_capacity(tokens.len());
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind == TokenKind::DocComment
+            && !is_decl_start(&tokens[i..])
+            && !is_impl_start(&tokens[i..])
+        {
+            continue;
         }
-        return Ok((input.clone(), ParseObj::List(result)));
+        kept.push(token.clone());
+    }
+    return kept;
+}
+
+// 顶层只有声明和 impl，和块里一样用 ; 分开，以 } 结尾的后面可以不写 ;，最后一个后面也可以不写
+fn program(tokens: &[Token]) -> TokenResult<'_> {
+    let mut tokens = tokens;
+    let mut items = Vec::new();
+    loop {
+        if tokens[0].kind == TokenKind::Eof {
+            return Ok((tokens, ParseObj::Program(items)));
+        }
+        if let Ok(rest) = expect(tokens, TokenKind::Punct, ";") {
+            tokens = rest;
+            continue;
+        }
+        let (rest, item) = if is_impl_start(tokens) {
+            documented_impl(tokens)?
+        } else if is_decl_start(tokens) {
+            decl(tokens)?
+        } else {
+            return Err(unexpected("declaration or `impl`", tokens));
+        };
+        if let Ok(after) = expect(rest, TokenKind::Punct, ";") {
+            tokens = after;
+        } else if rest[0].kind == TokenKind::Eof || is_block_like(&item) {
+            tokens = rest;
+        } else {
+            return Err(unexpected("`;` or end of file", rest));
+        }
+        items.push(item);
+    }
+}
+
+// 下一个 token 不是想要的东西，location 是它在源码里的字节偏移
+fn unexpected(expected: &str, tokens: &[Token]) -> ParseErr {
+    let token = &tokens[0];
+    let found = match token.kind {
+        TokenKind::Eof => "nothing".to_string(),
+        _ => token.text.clone(),
     };
+    return ParseErr::Unexpected(expected.to_string(), found, token.span.start as u64);
 }
 
-fn any_whitespace() -> impl Fn(String) -> ParseResult {
-    let sp = parse_char(' ');
-    let tab = parse_char('\t');
-    let newline = parse_char('\n');
-    return any_of(vec![sp, tab, newline]);
+// 匹配一个指定种类和原文的 token，比如 expect(tokens, TokenKind::Op, "=")
+fn expect<'a>(tokens: &'a [Token], kind: TokenKind, text: &str) -> Result<&'a [Token], ParseErr> {
+    if tokens[0].kind == kind && tokens[0].text == text {
+        return Ok(&tokens[1..]);
+    }
+    return Err(unexpected(&format!("`{}`", text), tokens));
 }
-fn whitespace() -> impl Fn(String) -> ParseResult {
-    return zero_or_more(any_whitespace());
+
+// 声明前面的 `///` 文档注释
+fn doc_comments(tokens: &[Token]) -> (&[Token], Vec<String>) {
+    let mut tokens = tokens;
+    let mut docs = Vec::new();
+    while tokens[0].kind == TokenKind::DocComment {
+        docs.push(tokens[0].text.clone());
+        tokens = &tokens[1..];
+    }
+    return (tokens, docs);
 }
 
-fn parse_char(c: char) -> impl Fn(String) -> ParseResult {
-    return move |input: String| {
-        if input.len() < 1 {
-            return ParseResult::Err(ParseErr::Unexpected(
-                c.to_string(),
-                "nothing".to_string(),
-                0,
-            ));
+// 有文档注释就把它挂到后面的声明上
+fn with_docs(docs: Vec<String>, obj: ParseObj) -> ParseObj {
+    if docs.is_empty() {
+        return obj;
+    }
+    return ParseObj::Doc(docs, Box::new(obj));
+}
+
+fn ident(tokens: &[Token]) -> TokenResult<'_> {
+    let token = &tokens[0];
+    return match token.kind {
+        TokenKind::Ident => Ok((&tokens[1..], ParseObj::Ident(token.text.clone()))),
+        TokenKind::Keyword => Err(ParseErr::Unexpected(
+            "identifier".to_string(),
+            format!("keyword `{}`", token.text),
+            token.span.start as u64,
+        )),
+        _ => Err(unexpected("identifier", tokens)),
+    };
+}
+
+fn bool(tokens: &[Token]) -> TokenResult<'_> {
+    let token = &tokens[0];
+    if token.kind == TokenKind::Keyword && (token.text == "true" || token.text == "false") {
+        return Ok((&tokens[1..], ParseObj::Bool(token.text == "true")));
+    }
+    return Err(unexpected("`true` or `false`", tokens));
+}
+
+// 字面量 token 交回 lexer 里对应的解析器求值
+// 紧跟在数字前面的 - + 并进字面量里，这样 -2 就是 Int(-2)，isize::MIN 也能写出来
+fn literal(tokens: &[Token]) -> TokenResult<'_> {
+    let mut sign = "";
+    let mut rest = tokens;
+    if tokens[0].kind == TokenKind::Op && (tokens[0].text == "-" || tokens[0].text == "+") {
+        if let TokenKind::Int | TokenKind::Float = tokens[1].kind {
+            sign = &tokens[0].text;
+            rest = &tokens[1..];
         }
-        if input.chars().nth(0).unwrap() == c.clone() {
-            return ParseResult::Ok((input[1..].to_string(), ParseObj::Char(c)));
+    }
+    let token = &rest[0];
+    let value = match token.kind {
+        TokenKind::Int | TokenKind::Float => lexer::number(format!("{}{}", sign, token.text)),
+        TokenKind::Str => lexer::string(token.text.clone()),
+        TokenKind::Char => lexer::char_literal(token.text.clone()),
+        _ => return Err(unexpected("literal", tokens)),
+    };
+    let start = tokens[0].span.start as u64;
+    let (_, obj) = value.map_err(|err| lexer::offset_err(err, start))?;
+    return Ok((&rest[1..], obj));
+}
+
+fn decl(tokens: &[Token]) -> TokenResult<'_> {
+    return decl_with(tokens, true);
+}
+
+// allow_struct 见 cond_expr，for 的 step 后面紧跟着循环体，值里不能有顶层的 struct 字面量
+fn decl_with(tokens: &[Token], allow_struct: bool) -> TokenResult<'_> {
+    // let ident: ty = expr，var 声明的可以重新赋值
+    // fn name(params): ty { } 是 let name = fn (params): ty { } 的简写
+    // enum Name { } 是 let Name = enum { } 的简写
+    let (tokens, docs) = doc_comments(tokens);
+    if is_keyword(tokens, "enum") && tokens[1].kind == TokenKind::Ident {
+        let name = tokens[1].text.clone();
+        let (tokens, e) = enum_body(&tokens[2..])?;
+        return Ok((
+            tokens,
+            with_docs(docs, ParseObj::Decl(name, Box::new(None), Box::new(e))),
+        ));
+    }
+    if is_keyword(tokens, "fn") && tokens[1].kind == TokenKind::Ident {
+        let name = tokens[1].text.clone();
+        let (tokens, f) = fn_signature_and_body(&tokens[2..])?;
+        return Ok((
+            tokens,
+            with_docs(docs, ParseObj::Decl(name, Box::new(None), Box::new(f))),
+        ));
+    }
+    let mutable = is_keyword(tokens, "var");
+    if !mutable && !is_keyword(tokens, "let") {
+        return Err(unexpected("`let`, `var` or `fn`", tokens));
+    }
+    let (tokens, obj) = ident(&tokens[1..])?;
+    let identifier = match obj {
+        ParseObj::Ident(i) => i,
+        _ => unreachable!(),
+    };
+    let mut tokens = tokens;
+    let mut ty: Option<Type> = None;
+    if let Ok(rest) = expect(tokens, TokenKind::Punct, ":") {
+        let (rest, t) = type_expr(rest)?;
+        tokens = rest;
+        ty = Some(t);
+    }
+    let tokens = expect(tokens, TokenKind::Op, "=")?;
+    let (tokens, e) = binary_expr(tokens, 0, allow_struct)?;
+    let decl = if mutable {
+        ParseObj::Var(identifier, Box::new(ty), Box::new(e))
+    } else {
+        ParseObj::Decl(identifier, Box::new(ty), Box::new(e))
+    };
+    return Ok((tokens, with_docs(docs, decl)));
+}
+
+// { 语句; 语句; 值 }
+// 以 ; 结尾的是语句，包成 Stmt；最后一个不带 ; 的表达式是整个块的值，原样放在 Block 的最后
+// 声明和赋值总是语句，在 } 前面可以不写 ;；以 } 结尾的（块、函数声明）后面也可以不写 ;
+fn block(tokens: &[Token]) -> TokenResult<'_> {
+    let mut tokens = expect(tokens, TokenKind::Punct, "{")?;
+    let mut stmts = Vec::new();
+    loop {
+        if let Ok(rest) = expect(tokens, TokenKind::Punct, "}") {
+            return Ok((rest, ParseObj::Block(stmts)));
         }
-        return ParseResult::Err(ParseErr::Unexpected(
-            c.to_string(),
-            input.chars().nth(0).unwrap().to_string(),
-            0,
+        if let Ok(rest) = expect(tokens, TokenKind::Punct, ";") {
+            tokens = rest;
+            continue;
+        }
+        let (rest, s) = stmt(tokens)?;
+        // 没有 else 的 if 没有值，放在块的最后也只是个语句
+        let no_value = is_binding(&s) || !has_else(&s);
+        if let Ok(rest) = expect(rest, TokenKind::Punct, ";") {
+            stmts.push(ParseObj::Stmt(Box::new(s)));
+            tokens = rest;
+        } else if expect(rest, TokenKind::Punct, "}").is_ok() {
+            stmts.push(if no_value {
+                ParseObj::Stmt(Box::new(s))
+            } else {
+                s
+            });
+            tokens = rest;
+        } else if is_block_like(&s) {
+            stmts.push(ParseObj::Stmt(Box::new(s)));
+            tokens = rest;
+        } else {
+            return Err(unexpected("`;` or `}`", rest));
+        }
+    }
+}
+
+// 以 } 结尾、作为语句时不需要 ; 的东西
+fn is_block_like(obj: &ParseObj) -> bool {
+    return match obj {
+        ParseObj::Block(_)
+        | ParseObj::If(_, _, _)
+        | ParseObj::ForC(_, _, _, _)
+        | ParseObj::ForEach(_, _, _)
+        | ParseObj::While(_, _)
+        | ParseObj::Loop(_)
+        | ParseObj::Labeled(_, _)
+        | ParseObj::Match(_, _)
+        | ParseObj::Impl(_, _, _) => true,
+        ParseObj::Decl(_, _, value) => matches!(**value, ParseObj::Fn(_, _, _) | ParseObj::Enum(_)),
+        ParseObj::Doc(_, inner) => is_block_like(inner),
+        _ => false,
+    };
+}
+
+// if / else if 链最后有没有 else，不是 if 的当作有
+fn has_else(obj: &ParseObj) -> bool {
+    return match obj {
+        ParseObj::If(_, _, otherwise) => match &**otherwise {
+            Some(next) => has_else(next),
+            None => false,
+        },
+        _ => true,
+    };
+}
+
+// 声明和赋值不是值，放在块的最后也不会变成块的值
+fn is_binding(obj: &ParseObj) -> bool {
+    return match obj {
+        ParseObj::Decl(_, _, _)
+        | ParseObj::Var(_, _, _)
+        | ParseObj::Assign(_, _)
+        | ParseObj::CompoundAssign(_, _, _) => true,
+        ParseObj::Doc(_, inner) => is_binding(inner),
+        _ => false,
+    };
+}
+
+fn stmt(tokens: &[Token]) -> TokenResult<'_> {
+    if is_impl_start(tokens) {
+        return documented_impl(tokens);
+    }
+    if is_decl_start(tokens) {
+        return decl(tokens);
+    }
+    // 语句开头的块到 } 就结束，后面的 ( [ - 是下一个语句的开头，不能接到块上
+    if expect(tokens, TokenKind::Punct, "{").is_ok() {
+        return block(tokens);
+    }
+    // 语句开头的循环也一样到 } 就结束
+    if is_keyword(tokens, "for") {
+        return for_loop(tokens);
+    }
+    if is_keyword(tokens, "while") {
+        return while_loop(tokens);
+    }
+    if is_keyword(tokens, "loop") {
+        return loop_expr(tokens);
+    }
+    if tokens[0].kind == TokenKind::Label {
+        return labeled_loop(tokens);
+    }
+    if is_keyword(tokens, "match") {
+        return match_expr(tokens);
+    }
+    // 作为语句的 if 可以没有 else
+    if is_keyword(tokens, "if") {
+        return if_expr(tokens, false);
+    }
+    if is_keyword(tokens, "break") || is_keyword(tokens, "continue") {
+        return break_or_continue(tokens);
+    }
+    if is_keyword(tokens, "return") {
+        let (rest, value) = optional_value(&tokens[1..])?;
+        return Ok((rest, ParseObj::Return(Box::new(value))));
+    }
+    return assign_or_expr(tokens, true);
+}
+
+// 表达式后面跟着 = 或者 += 之类的就是赋值，左边必须是能赋值的位置
+fn assign_or_expr(tokens: &[Token], allow_struct: bool) -> TokenResult<'_> {
+    let start = tokens[0].span.start as u64;
+    let (tokens, target) = binary_expr(tokens, 0, allow_struct)?;
+    let op = &tokens[0];
+    let compound = match op.text.as_str() {
+        "=" | "+=" | "-=" | "*=" | "/=" | "%=" if op.kind == TokenKind::Op => op.text != "=",
+        _ => return Ok((tokens, target)),
+    };
+    if !matches!(
+        target,
+        ParseObj::Ident(_) | ParseObj::Field(_, _) | ParseObj::Index(_, _)
+    ) {
+        return Err(ParseErr::Unexpected(
+            format!("variable, field or index before `{}`", op.text),
+            "expression".to_string(),
+            start,
         ));
+    }
+    let (rest, value) = binary_expr(&tokens[1..], 0, allow_struct)?;
+    if compound {
+        let bin_op = op.text.trim_end_matches('=').to_string();
+        return Ok((
+            rest,
+            ParseObj::CompoundAssign(bin_op, Box::new(target), Box::new(value)),
+        ));
+    }
+    return Ok((rest, ParseObj::Assign(Box::new(target), Box::new(value))));
+}
+
+// break / continue，后面可以跟一个标签，break 最后还可以跟一个值
+// 只能写在循环体里，这个解析完以后由 check_jumps 检查
+fn break_or_continue(tokens: &[Token]) -> TokenResult<'_> {
+    let keyword = &tokens[0].text;
+    let location = tokens[0].span.start as u64;
+    let mut tokens = &tokens[1..];
+    let mut label = None;
+    if tokens[0].kind == TokenKind::Label {
+        label = Some(tokens[0].text[1..].to_string());
+        tokens = &tokens[1..];
+    }
+    if keyword == "continue" {
+        return Ok((tokens, ParseObj::Continue(label, location)));
+    }
+    let (tokens, value) = optional_value(tokens)?;
+    return Ok((tokens, ParseObj::Break(label, Box::new(value), location)));
+}
+
+// return / break 后面的值，后面直接是 ; } , 或者文件结尾就是没有值（, 是 match 分支之间的）
+fn optional_value(tokens: &[Token]) -> Result<(&[Token], Option<ParseObj>), ParseErr> {
+    let token = &tokens[0];
+    let ends = token.kind == TokenKind::Eof
+        || (token.kind == TokenKind::Punct
+            && (token.text == ";" || token.text == "}" || token.text == ","));
+    if ends {
+        return Ok((tokens, None));
+    }
+    let (tokens, value) = expr(tokens)?;
+    return Ok((tokens, Some(value)));
+}
+
+// 解析完以后整棵树走一遍，找不在循环体里的 break / continue
+fn check_jumps(obj: &ParseObj) -> Result<(), ParseErr> {
+    return match stray_jump(obj, false) {
+        Some(err) => Err(err),
+        None => Ok(()),
     };
 }
 
-fn one_or_more(parser: impl Fn(String) -> ParseResult) -> impl Fn(String) -> ParseResult {
-    return move |mut input: String| {
-        let mut result = Vec::new();
+fn outside_loop(keyword: &str, location: u64) -> ParseErr {
+    return ParseErr::Unexpected(
+        format!("`{}` inside a loop", keyword),
+        format!("`{}` outside of any loop", keyword),
+        location,
+    );
+}
 
-        // we should first try to get one, if can't it's a parse error
-        match parser(input.clone()) {
-            Ok((remains, parsed)) => {
-                input = remains;
-                result.push(parsed);
-            }
-            Err(err) => {
-                return Err(err);
-            }
+// 按源码顺序找第一个不在循环里的 break / continue
+// 循环体里 in_loop 是 true，函数体里重新变成 false，外面的循环管不到函数里面
+fn stray_jump(obj: &ParseObj, in_loop: bool) -> Option<ParseErr> {
+    let each = |objs: &[&ParseObj], in_loop: bool| {
+        return objs.iter().find_map(|o| stray_jump(o, in_loop));
+    };
+    return match obj {
+        ParseObj::Break(_, _, location) if !in_loop => Some(outside_loop("break", *location)),
+        ParseObj::Continue(_, location) if !in_loop => Some(outside_loop("continue", *location)),
+        ParseObj::Break(_, value, _) | ParseObj::Return(value) => {
+            (**value).as_ref().and_then(|v| stray_jump(v, in_loop))
+        }
+        ParseObj::List(items)
+        | ParseObj::ArrayLit(items)
+        | ParseObj::Block(items)
+        | ParseObj::Program(items) => items.iter().find_map(|o| stray_jump(o, in_loop)),
+        ParseObj::Impl(_, target, methods) => stray_jump_in_type(target, in_loop)
+            .or_else(|| methods.iter().find_map(|o| stray_jump(o, in_loop))),
+        ParseObj::Decl(_, ty, value) | ParseObj::Var(_, ty, value) => (**ty)
+            .as_ref()
+            .and_then(|ty| stray_jump_in_type(ty, in_loop))
+            .or_else(|| stray_jump(value, in_loop)),
+        ParseObj::Unary(_, value)
+        | ParseObj::Field(value, _)
+        | ParseObj::Stmt(value)
+        | ParseObj::Doc(_, value)
+        | ParseObj::Labeled(_, value) => stray_jump(value, in_loop),
+        ParseObj::Assign(lhs, rhs)
+        | ParseObj::CompoundAssign(_, lhs, rhs)
+        | ParseObj::Index(lhs, rhs)
+        | ParseObj::ArrayRepeat(lhs, rhs)
+        | ParseObj::Binary(_, lhs, rhs) => each(&[lhs, rhs], in_loop),
+        ParseObj::FnCall(callee, args) | ParseObj::MethodCall(callee, _, args) => {
+            stray_jump(callee, in_loop).or_else(|| args.iter().find_map(|o| stray_jump(o, in_loop)))
+        }
+        ParseObj::Struct(fields) => fields
+            .iter()
+            .find_map(|(_, ty)| stray_jump_in_type(ty, in_loop)),
+        ParseObj::StructLit(_, fields) => fields.iter().find_map(|(_, v)| stray_jump(v, in_loop)),
+        ParseObj::Enum(variants) => variants.iter().find_map(|v| match v {
+            Variant::Unit(_) => None,
+            Variant::Tuple(_, types) => types.iter().find_map(|ty| stray_jump_in_type(ty, in_loop)),
+            Variant::Struct(_, fields) => fields
+                .iter()
+                .find_map(|(_, ty)| stray_jump_in_type(ty, in_loop)),
+        }),
+        ParseObj::Fn(params, ret, body) => params
+            .iter()
+            .find_map(|(_, ty)| stray_jump_in_type(ty, in_loop))
+            .or_else(|| {
+                (**ret)
+                    .as_ref()
+                    .and_then(|ty| stray_jump_in_type(ty, in_loop))
+            })
+            .or_else(|| stray_jump(body, false)),
+        ParseObj::Interface(methods) => {
+            methods.iter().find_map(|(_, sig)| stray_jump(sig, in_loop))
+        }
+        ParseObj::If(cond, then, otherwise) => each(&[cond, then], in_loop)
+            .or_else(|| (**otherwise).as_ref().and_then(|o| stray_jump(o, in_loop))),
+        ParseObj::ForC(init, cond, step, body) => {
+            each(&[init, cond, step], in_loop).or_else(|| stray_jump(body, true))
         }
-        while let Ok((remains, parsed)) = parser(input.clone()) {
-            input = remains;
-            result.push(parsed);
+        ParseObj::ForEach(_, iterable, body) | ParseObj::While(iterable, body) => {
+            stray_jump(iterable, in_loop).or_else(|| stray_jump(body, true))
         }
-        return Ok((input.clone(), ParseObj::List(result)));
+        ParseObj::Loop(body) => stray_jump(body, true),
+        ParseObj::Match(scrutinee, arms) => stray_jump(scrutinee, in_loop)
+            .or_else(|| arms.iter().find_map(|o| stray_jump(o, in_loop))),
+        ParseObj::Arm(_, guard, body) => (**guard)
+            .as_ref()
+            .and_then(|g| stray_jump(g, in_loop))
+            .or_else(|| stray_jump(body, in_loop)),
+        _ => None,
     };
-} 
-fn parse_chars(chars: &str) -> impl Fn(String) -> ParseResult {
-    let parsers = chars.chars().map(|c| parse_char(c)).collect();
+}
 
-    let res1 = chars.chars().map(|c| parse_char(c));
-    println!("chars.chars().map(|c| parse_char {:?}", res1);
+// 类型里也可能有表达式: [n]int 的长度
+fn stray_jump_in_type(ty: &Type, in_loop: bool) -> Option<ParseErr> {
+    return match ty {
+        Type::Named(_) => None,
+        Type::Generic(_, args) => args.iter().find_map(|t| stray_jump_in_type(t, in_loop)),
+        Type::Array(len, elem) => (**len)
+            .as_ref()
+            .and_then(|l| stray_jump(l, in_loop))
+            .or_else(|| stray_jump_in_type(elem, in_loop)),
+        Type::Fn(params, ret) => params
+            .iter()
+            .find_map(|t| stray_jump_in_type(t, in_loop))
+            .or_else(|| {
+                (**ret)
+                    .as_ref()
+                    .and_then(|t| stray_jump_in_type(t, in_loop))
+            }),
+        Type::Optional(inner) => stray_jump_in_type(inner, in_loop),
+        Type::Struct(fields) => fields
+            .iter()
+            .find_map(|(_, t)| stray_jump_in_type(t, in_loop)),
+        Type::Interface(methods) => methods.iter().find_map(|(_, sig)| stray_jump(sig, in_loop)),
+    };
+}
 
-    return any_of(parsers);
+// let ... / var ... / fn name(...) / enum Name { } / 前面带文档注释，都是声明
+fn is_decl_start(tokens: &[Token]) -> bool {
+    return match tokens[0].kind {
+        TokenKind::DocComment => is_decl_start(doc_comments(tokens).0),
+        TokenKind::Keyword => match tokens[0].text.as_str() {
+            "let" | "var" => true,
+            "fn" | "enum" => tokens[1].kind == TokenKind::Ident,
+            _ => false,
+        },
+        _ => false,
+    };
 }
 
-fn any_of(parsers: Vec<impl Fn(String) -> ParseResult>) -> impl Fn(String) -> ParseResult {
-    return move |input: String| {
-        for parser in parsers.iter() {
-            match parser(input.clone()) {
-                Ok((remaining, parsed)) => return Ok((remaining, parsed)),
-                Err(err) => continue,
-            }
-        }
-        return Err(ParseErr::Unexpected("".to_string(), "".to_string(), 0));
+// impl 前面也可以有文档注释，跳过注释再看
+fn is_impl_start(tokens: &[Token]) -> bool {
+    return is_keyword(doc_comments(tokens).0, "impl");
+}
+
+fn documented_impl(tokens: &[Token]) -> TokenResult<'_> {
+    let (tokens, docs) = doc_comments(tokens);
+    let (rest, obj) = impl_block(tokens)?;
+    return Ok((rest, with_docs(docs, obj)));
+}
+
+fn is_keyword(tokens: &[Token], word: &str) -> bool {
+    return tokens[0].kind == TokenKind::Keyword && tokens[0].text == word;
+}
+
+fn expr(tokens: &[Token]) -> TokenResult<'_> {
+    return binary_expr(tokens, 0, true);
+}
+
+// if / 循环的条件: 不允许顶层的 struct 字面量，不然 if x { ... } 的 x { 会被当成 struct 字面量
+// 括号、方括号、块里面又可以写了
+fn cond_expr(tokens: &[Token]) -> TokenResult<'_> {
+    return binary_expr(tokens, 0, false);
+}
+
+// 二元运算符的优先级，数字越大结合得越紧
+// 区间 a..b（不含 b）和 a..=b（含 b）也当成二元运算，优先级最低
+fn binary_precedence(op: &str) -> Option<u8> {
+    return match op {
+        ".." | "..=" => Some(1),
+        "||" => Some(2),
+        "&&" => Some(3),
+        "==" | "!=" => Some(4),
+        "<" | "<=" | ">" | ">=" => Some(5),
+        "+" | "-" => Some(6),
+        "*" | "/" | "%" => Some(7),
+        _ => None,
     };
 }
 
-fn ident(input: String) -> ParseResult {
-    match one_or_more(parse_chars(
-        "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_",
-    ))(input)
-    {
-        Ok((remains, ParseObj::List(chars_parse_objects))) => {
-            let mut name = String::new();
+// precedence climbing: 先拿一个一元表达式，再把优先级不低于 min_prec 的运算符都吃掉
+// 右边用 prec + 1 递归，所以同级的运算符是左结合的: a - b - c 是 (a - b) - c
+fn binary_expr(tokens: &[Token], min_prec: u8, allow_struct: bool) -> TokenResult<'_> {
+    let (mut tokens, mut lhs) = unary_expr(tokens, allow_struct)?;
+    loop {
+        let op = &tokens[0];
+        let prec = match binary_precedence(&op.text) {
+            Some(prec) if op.kind == TokenKind::Op && prec >= min_prec => prec,
+            _ => break,
+        };
+        let (rest, rhs) = binary_expr(&tokens[1..], prec + 1, allow_struct)?;
+        lhs = ParseObj::Binary(op.text.clone(), Box::new(lhs), Box::new(rhs));
+        tokens = rest;
+    }
+    return Ok((tokens, lhs));
+}
 
-            for po in chars_parse_objects {
-                match po {
-                    ParseObj::Char(c) => name.push(c),
-                    _ => {
-                        return Err(ParseErr::Unexpected(
-                            "a char".to_string(),
-                            format!("{:?}", po),
-                            0,
-                        ))
+// 前缀的 ! 和 -，-2 这种直接跟数字的交给 literal 变成负数字面量
+// 后缀比前缀结合得紧: -a.b 是 -(a.b)，-2.5.floor() 也是 -(2.5.floor())
+fn unary_expr(tokens: &[Token], allow_struct: bool) -> TokenResult<'_> {
+    let token = &tokens[0];
+    if token.kind == TokenKind::Op && (token.text == "!" || token.text == "-") {
+        if let TokenKind::Int | TokenKind::Float = tokens[1].kind {
+            if token.text == "-" && !is_postfix_start(&tokens[2..]) {
+                return postfix_expr(tokens, allow_struct);
+            }
+        }
+        let (tokens, operand) = unary_expr(&tokens[1..], allow_struct)?;
+        return Ok((
+            tokens,
+            ParseObj::Unary(token.text.clone(), Box::new(operand)),
+        ));
+    }
+    return postfix_expr(tokens, allow_struct);
+}
+
+// 后缀表达式: 调用 f(args)，字段 p.x，下标 list[i]，方法调用 s.len()，可以一直接下去: a.b[c].d()
+// .name 后面紧跟 ( 的是方法调用，(a.b)(1) 才是调用字段里的函数
+fn postfix_expr(tokens: &[Token], allow_struct: bool) -> TokenResult<'_> {
+    let (mut tokens, mut obj) = primary(tokens, allow_struct)?;
+    while is_postfix_start(tokens) {
+        let (rest, next) = match tokens[0].text.as_str() {
+            "(" => {
+                let (rest, args) = comma_list(&tokens[1..], ")", expr)?;
+                (rest, ParseObj::FnCall(Box::new(obj), args))
+            }
+            "[" => {
+                let (rest, index) = expr(&tokens[1..])?;
+                let rest = expect(rest, TokenKind::Punct, "]")?;
+                (rest, ParseObj::Index(Box::new(obj), Box::new(index)))
+            }
+            _ => {
+                let rest = &tokens[1..];
+                let name = match rest[0].kind {
+                    TokenKind::Ident => rest[0].text.clone(),
+                    _ => return Err(unexpected("field or method name after `.`", rest)),
+                };
+                match expect(&rest[1..], TokenKind::Punct, "(") {
+                    Ok(rest) => {
+                        let (rest, args) = comma_list(rest, ")", expr)?;
+                        (rest, ParseObj::MethodCall(Box::new(obj), name, args))
                     }
+                    Err(_) => (&rest[1..], ParseObj::Field(Box::new(obj), name)),
                 }
             }
-            return Ok((remains, ParseObj::Ident(name)));
-        }
-        // 匹配只有一个字符作为标志符的情况
-        Ok((_, obj)) => {
-            return Err(ParseErr::Unexpected(
-                "list of chars".to_string(),
-                format!("{:?}", obj),
-                0,
-            ))
+        };
+        obj = next;
+        tokens = rest;
+    }
+    return Ok((tokens, obj));
+}
+
+fn is_postfix_start(tokens: &[Token]) -> bool {
+    return tokens[0].kind == TokenKind::Punct
+        && (tokens[0].text == "(" || tokens[0].text == "[" || tokens[0].text == ".");
+}
+
+// 逗号分隔的列表，一直到 close 为止（close 也吃掉），可以为空，允许结尾多一个逗号
+fn comma_list<'a, T>(
+    tokens: &'a [Token],
+    close: &str,
+    item: impl Fn(&'a [Token]) -> Result<(&'a [Token], T), ParseErr>,
+) -> Result<(&'a [Token], Vec<T>), ParseErr> {
+    let mut tokens = tokens;
+    let mut items = Vec::new();
+    loop {
+        if let Ok(rest) = expect(tokens, TokenKind::Punct, close) {
+            return Ok((rest, items));
         }
-        Err(err) => Err(err),
+        let (rest, parsed) = item(tokens)?;
+        items.push(parsed);
+        tokens = match expect(rest, TokenKind::Punct, ",") {
+            Ok(rest) => rest,
+            Err(_) => {
+                let rest = expect(rest, TokenKind::Punct, close)
+                    .map_err(|_| unexpected(&format!("`,` or `{}`", close), rest))?;
+                return Ok((rest, items));
+            }
+        };
     }
 }
 
-fn decl(mut input: String) -> ParseResult {
-    // ident: expr = expr;
-    // 1.去掉前面的换行空格和缩进
-    let (remains, _) = whitespace()(input.clone())?;
-
-    println!("whitespace remains{:?}", remains);
-    // 2.获取变量
-    let (remains, obj) = ident(remains)?;
-    println!("ident remains{:?}", remains);
-    let mut identifier = "".to_string();
-    match obj {
-        ParseObj::Ident(i) => identifier = i,
-        _ => {
-            return Err(ParseErr::Unexpected(
-                "ident".to_string(),
-                format!("{:?}", obj),
-                0,
-            ))
+fn primary(tokens: &[Token], allow_struct: bool) -> TokenResult<'_> {
+    // bool
+    // ident
+    // String
+    // int, uint, float
+    // (expr)
+    // { block }
+    // if
+    // for / while / loop
+    // match
+    // fn_def
+
+    // 按第一个 token 的种类决定走哪条规则，字面量溢出之类的错误就能原样报出来
+    let token = &tokens[0];
+    return match token.kind {
+        TokenKind::Int | TokenKind::Float | TokenKind::Str | TokenKind::Char => literal(tokens),
+        TokenKind::Op if token.text == "-" || token.text == "+" => literal(tokens),
+        TokenKind::Keyword if token.text == "fn" => fn_def(tokens),
+        TokenKind::Keyword if token.text == "struct" => struct_type(tokens),
+        TokenKind::Keyword if token.text == "enum" => enum_body(&tokens[1..]),
+        TokenKind::Keyword if token.text == "interface" => interface_type(tokens),
+        TokenKind::Keyword if token.text == "if" => if_expr(tokens, true),
+        TokenKind::Keyword if token.text == "for" => for_loop(tokens),
+        TokenKind::Keyword if token.text == "while" => while_loop(tokens),
+        TokenKind::Keyword if token.text == "loop" => loop_expr(tokens),
+        TokenKind::Keyword if token.text == "match" => match_expr(tokens),
+        TokenKind::Label => labeled_loop(tokens),
+        TokenKind::Keyword => bool(tokens),
+        TokenKind::Ident if is_path(tokens) => {
+            let (rest, p) = path(tokens)?;
+            match expect(rest, TokenKind::Punct, "{") {
+                Ok(fields) if allow_struct => struct_lit_fields(fields, p),
+                _ => Ok((rest, p)),
+            }
         }
+        TokenKind::Ident
+            if allow_struct && tokens[1].kind == TokenKind::Punct && tokens[1].text == "{" =>
+        {
+            struct_lit(tokens)
+        }
+        TokenKind::Ident => ident(tokens),
+        TokenKind::Punct if token.text == "[" => array_lit(tokens),
+        TokenKind::Punct if token.text == "{" => block(tokens),
+        TokenKind::Punct if token.text == "(" => {
+            let (tokens, e) = expr(&tokens[1..])?;
+            let tokens = expect(tokens, TokenKind::Punct, ")")?;
+            Ok((tokens, e))
+        }
+        _ => Err(unexpected("expression", tokens)),
+    };
+}
+
+// if cond { } else if cond { } else { }
+// 当值用的 if (need_else) 必须有 else，不然没有条件不满足时的值
+fn if_expr(tokens: &[Token], need_else: bool) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "if")?;
+    let (tokens, cond) = cond_expr(tokens)?;
+    let (tokens, then) = block(tokens)?;
+    let (tokens, otherwise) = if is_keyword(tokens, "else") {
+        let rest = &tokens[1..];
+        let (rest, otherwise) = if is_keyword(rest, "if") {
+            if_expr(rest, need_else)?
+        } else {
+            block(rest)?
+        };
+        (rest, Some(otherwise))
+    } else if need_else {
+        return Err(unexpected("`else` branch for if used as a value", tokens));
+    } else {
+        (tokens, None)
+    };
+    return Ok((
+        tokens,
+        ParseObj::If(Box::new(cond), Box::new(then), Box::new(otherwise)),
+    ));
+}
+
+// for var i = 0; i < 10; i += 1 { }，三个部分都可以不写: for ;; { }
+// for x in xs { } 是 foreach
+fn for_loop(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "for")?;
+    if tokens[0].kind == TokenKind::Ident && is_keyword(&tokens[1..], "in") {
+        let (rest, var) = ident(tokens)?;
+        let (rest, iterable) = cond_expr(&rest[1..])?;
+        let (rest, body) = block(rest)?;
+        return Ok((
+            rest,
+            ParseObj::ForEach(Box::new(var), Box::new(iterable), Box::new(body)),
+        ));
     }
-    println!("ident: {} remains: \"{}\"", identifier, remains);
-    // 继续去掉空格
-    let (mut remains, _) = whitespace()(remains)?;
-    let mut ty: Option<ParseObj> = None;
-    let colon_res = parse_char(':')(remains.clone());
-    println!("colon_res: {:?}", colon_res);
-    match colon_res {
-        Ok((r, ParseObj::Char(':'))) => {
-            let ty_res = expr(r)?;
-            remains = ty_res.0;
-            ty = Some(ty_res.1);
+    let (tokens, init) = match expect(tokens, TokenKind::Punct, ";") {
+        Ok(_) => (tokens, ParseObj::Empty),
+        Err(_) => for_clause(tokens, true)?,
+    };
+    let tokens = expect(tokens, TokenKind::Punct, ";")?;
+    let (tokens, cond) = match expect(tokens, TokenKind::Punct, ";") {
+        Ok(_) => (tokens, ParseObj::Empty),
+        Err(_) => expr(tokens)?,
+    };
+    let tokens = expect(tokens, TokenKind::Punct, ";")?;
+    let (tokens, step) = match expect(tokens, TokenKind::Punct, "{") {
+        Ok(_) => (tokens, ParseObj::Empty),
+        Err(_) => for_clause(tokens, false)?,
+    };
+    let (tokens, body) = block(tokens)?;
+    return Ok((
+        tokens,
+        ParseObj::ForC(
+            Box::new(init),
+            Box::new(cond),
+            Box::new(step),
+            Box::new(body),
+        ),
+    ));
+}
+
+fn while_loop(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "while")?;
+    let (tokens, cond) = cond_expr(tokens)?;
+    let (tokens, body) = block(tokens)?;
+    return Ok((tokens, ParseObj::While(Box::new(cond), Box::new(body))));
+}
+
+fn loop_expr(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "loop")?;
+    let (tokens, body) = block(tokens)?;
+    return Ok((tokens, ParseObj::Loop(Box::new(body))));
+}
+
+// 'outer: for / while / loop
+fn labeled_loop(tokens: &[Token]) -> TokenResult<'_> {
+    let label = tokens[0].text[1..].to_string();
+    let tokens = expect(&tokens[1..], TokenKind::Punct, ":")?;
+    let (tokens, body) = match tokens[0].text.as_str() {
+        "for" if tokens[0].kind == TokenKind::Keyword => for_loop(tokens)?,
+        "while" if tokens[0].kind == TokenKind::Keyword => while_loop(tokens)?,
+        "loop" if tokens[0].kind == TokenKind::Keyword => loop_expr(tokens)?,
+        _ => return Err(unexpected("`for`, `while` or `loop` after a label", tokens)),
+    };
+    return Ok((tokens, ParseObj::Labeled(label, Box::new(body))));
+}
+
+// match x { 1 => a, Point { x, .. } if x > 0 => { b } _ => c }
+// 分支之间用逗号分开，值是块的分支后面可以不写逗号
+fn match_expr(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "match")?;
+    let (tokens, scrutinee) = cond_expr(tokens)?;
+    let mut tokens = expect(tokens, TokenKind::Punct, "{")?;
+    let mut arms = Vec::new();
+    loop {
+        if let Ok(rest) = expect(tokens, TokenKind::Punct, "}") {
+            return Ok((rest, ParseObj::Match(Box::new(scrutinee), arms)));
         }
+        let (rest, arm) = match_arm(tokens)?;
+        let block_body =
+            matches!(&arm, ParseObj::Arm(_, _, body) if matches!(**body, ParseObj::Block(_)));
+        arms.push(arm);
+        tokens = match expect(rest, TokenKind::Punct, ",") {
+            Ok(rest) => rest,
+            Err(_) if block_body || expect(rest, TokenKind::Punct, "}").is_ok() => rest,
+            Err(_) => return Err(unexpected("`,` or `}`", rest)),
+        };
+    }
+}
+
+// 模式 [if 守卫] => 值，值也可以是 return / break / continue
+// 值是块的时候块后面就结束了，下一个分支的 [ 或 ( 不能当成下标或调用接上去
+fn match_arm(tokens: &[Token]) -> TokenResult<'_> {
+    let (tokens, pat) = pattern(tokens)?;
+    let (tokens, guard) = if is_keyword(tokens, "if") {
+        let (rest, guard) = expr(&tokens[1..])?;
+        (rest, Some(guard))
+    } else {
+        (tokens, None)
+    };
+    let tokens = expect(tokens, TokenKind::Op, "=>")?;
+    let (tokens, body) = match tokens[0].text.as_str() {
+        "return" | "break" | "continue" if tokens[0].kind == TokenKind::Keyword => stmt(tokens)?,
+        "{" if tokens[0].kind == TokenKind::Punct => block(tokens)?,
+        _ => expr(tokens)?,
+    };
+    return Ok((
+        tokens,
+        ParseObj::Arm(Box::new(pat), Box::new(guard), Box::new(body)),
+    ));
+}
+
+fn pattern(tokens: &[Token]) -> Result<(&[Token], Pattern), ParseErr> {
+    let token = &tokens[0];
+    match token.kind {
+        TokenKind::Ident if token.text == "_" => return Ok((&tokens[1..], Pattern::Wildcard)),
+        TokenKind::Ident if is_path(tokens) => return variant_pattern(tokens),
+        TokenKind::Ident if tokens[1].kind == TokenKind::Punct && tokens[1].text == "{" => {
+            return struct_pattern(&tokens[2..], ParseObj::Ident(token.text.clone()));
+        }
+        TokenKind::Ident => return Ok((&tokens[1..], Pattern::Binding(token.text.clone()))),
+        TokenKind::Punct if token.text == "[" => {
+            let (rest, elems) = comma_list(&tokens[1..], "]", pattern)?;
+            return Ok((rest, Pattern::Array(elems)));
+        }
+        TokenKind::Op if token.text == ".." => return Ok((&tokens[1..], Pattern::Rest)),
         _ => {}
     }
-    let (remains, _) = parse_char('=')(remains)?;
-    let (remains, _) = whitespace()(remains)?;
-    println!("remains: \"{}\"", remains);
-    let (remains, e) = expr(remains)?;
-    println!("expr: {:?} remains: \"{}\"", e, remains);
+    let (rest, lo) = match token.kind {
+        TokenKind::Keyword if token.text == "true" || token.text == "false" => bool(tokens)?,
+        TokenKind::Int | TokenKind::Float | TokenKind::Str | TokenKind::Char => literal(tokens)?,
+        TokenKind::Op if token.text == "-" || token.text == "+" => literal(tokens)?,
+        _ => return Err(unexpected("pattern", tokens)),
+    };
+    // 1..10 / 1..=10，上界也必须是字面量
+    let op = &rest[0];
+    if op.kind == TokenKind::Op && (op.text == ".." || op.text == "..=") {
+        let (rest, hi) = literal(&rest[1..])?;
+        return Ok((
+            rest,
+            Pattern::Range(Box::new(lo), Box::new(hi), op.text == "..="),
+        ));
+    }
+    return Ok((rest, Pattern::Literal(Box::new(lo))));
+}
+
+// Shape::Circle(r) / Shape::Rect { w, .. } / Shape::Empty
+fn variant_pattern(tokens: &[Token]) -> Result<(&[Token], Pattern), ParseErr> {
+    let (tokens, p) = path(tokens)?;
+    if let Ok(rest) = expect(tokens, TokenKind::Punct, "{") {
+        return struct_pattern(rest, p);
+    }
+    let ParseObj::Path(enum_name, variant) = p else {
+        unreachable!()
+    };
+    let (tokens, elems) = match expect(tokens, TokenKind::Punct, "(") {
+        Ok(rest) => comma_list(rest, ")", pattern)?,
+        Err(_) => (tokens, vec![]),
+    };
+    return Ok((tokens, Pattern::Variant(enum_name, variant, elems)));
+}
+
+// Point { x: 0, y, .. }，.. 只能写在最后
+// { 已经吃掉了，name 是 Ident 或者 Path
+fn struct_pattern(tokens: &[Token], name: ParseObj) -> Result<(&[Token], Pattern), ParseErr> {
+    let mut tokens = tokens;
+    let mut fields = Vec::new();
+    let mut rest_fields = false;
+    loop {
+        if let Ok(rest) = expect(tokens, TokenKind::Punct, "}") {
+            return Ok((rest, Pattern::Struct(Box::new(name), fields, rest_fields)));
+        }
+        if let Ok(rest) = expect(tokens, TokenKind::Op, "..") {
+            rest_fields = true;
+            tokens = rest;
+            continue;
+        }
+        if rest_fields {
+            return Err(unexpected("`}` after `..`", tokens));
+        }
+        let (rest, field) = ident(tokens)?;
+        let ParseObj::Ident(field) = field else {
+            unreachable!()
+        };
+        let (rest, pat) = match expect(rest, TokenKind::Punct, ":") {
+            Ok(rest) => pattern(rest)?,
+            Err(_) => (rest, Pattern::Binding(field.clone())),
+        };
+        fields.push((field, pat));
+        tokens = match expect(rest, TokenKind::Punct, ",") {
+            Ok(rest) => rest,
+            Err(_) => match expect(rest, TokenKind::Punct, "}") {
+                Ok(_) => rest,
+                Err(_) => return Err(unexpected("`,` or `}`", rest)),
+            },
+        };
+    }
+}
+
+// for 头部的 init 和 step: 声明、赋值或者表达式
+fn for_clause(tokens: &[Token], allow_struct: bool) -> TokenResult<'_> {
+    if is_decl_start(tokens) {
+        return decl_with(tokens, allow_struct);
+    }
+    return assign_or_expr(tokens, allow_struct);
+}
+
+// 函数定义 fn (a: int, b: int): int { ... }，返回类型可以不写
+fn fn_def(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "fn")?;
+    return fn_signature_and_body(tokens);
+}
+
+// fn 关键字（和函数名）后面的 (params): ty { body }
+fn fn_signature_and_body(tokens: &[Token]) -> TokenResult<'_> {
+    let (tokens, signature) = fn_signature(tokens)?;
+    let (tokens, body) = block(tokens)?;
+    let ParseObj::Fn(params, ret, _) = signature else {
+        unreachable!()
+    };
+    return Ok((tokens, ParseObj::Fn(params, ret, Box::new(body))));
+}
+
+// (params): ty，得到一个 body 为 Empty 的 Fn
+fn fn_signature(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Punct, "(")?;
+    let (tokens, params) = comma_list(tokens, ")", param)?;
+    let (tokens, ret) = return_type(tokens)?;
     return Ok((
-        remains,
-        ParseObj::Decl(identifier, Box::new(ty), Box::new(e)),
+        tokens,
+        ParseObj::Fn(params, Box::new(ret), Box::new(ParseObj::Empty)),
     ));
 }
 
-fn keyword(word: String) -> impl Fn(String) -> ParseResult {
-    return move |mut input: String| {
-        let word_chars = word.chars();
-        for c in word_chars {
-            match parse_char(c)(input) {
-                Ok((remains, _)) => input = remains,
-                Err(err) => return Err(err),
+// interface { area(): float, scale(by: float) }，方法之间和 struct 字段一样用逗号分开
+fn interface_type(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "interface")?;
+    let tokens = expect(tokens, TokenKind::Punct, "{")?;
+    let (tokens, methods) = comma_list(tokens, "}", method_signature)?;
+    return Ok((tokens, ParseObj::Interface(methods)));
+}
+
+fn method_signature(tokens: &[Token]) -> Result<(&[Token], (ParseObj, ParseObj)), ParseErr> {
+    let (tokens, name) = ident(tokens)?;
+    let (tokens, signature) = fn_signature(tokens)?;
+    return Ok((tokens, (name, signature)));
+}
+
+// impl Shape for Circle { fn area(): float { ... } }
+// 里面只能写 fn name() {} 形式的方法，可以带文档注释
+fn impl_block(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "impl")?;
+    let (tokens, interface) = ident(tokens)?;
+    let tokens = expect(tokens, TokenKind::Keyword, "for")?;
+    let (tokens, target) = type_expr(tokens)?;
+    let mut tokens = expect(tokens, TokenKind::Punct, "{")?;
+    let mut methods = Vec::new();
+    loop {
+        if let Ok(rest) = expect(tokens, TokenKind::Punct, "}") {
+            return Ok((
+                rest,
+                ParseObj::Impl(Box::new(interface), Box::new(target), methods),
+            ));
+        }
+        let (rest, _) = doc_comments(tokens);
+        if !(is_keyword(rest, "fn") && rest[1].kind == TokenKind::Ident) {
+            return Err(unexpected("method `fn name(...) { }` or `}`", rest));
+        }
+        let (rest, method) = decl(tokens)?;
+        methods.push(method);
+        tokens = rest;
+    }
+}
+
+// 参数 name: ty，类型必须写
+fn param(tokens: &[Token]) -> Result<(&[Token], (ParseObj, Type)), ParseErr> {
+    let (tokens, name) = ident(tokens)?;
+    let tokens = expect(tokens, TokenKind::Punct, ":")?;
+    let (tokens, t) = type_expr(tokens)?;
+    return Ok((tokens, (name, t)));
+}
+
+// 可选的 : ty，没写返回类型就是 None
+fn return_type(tokens: &[Token]) -> Result<(&[Token], Option<Type>), ParseErr> {
+    return match expect(tokens, TokenKind::Punct, ":") {
+        Ok(rest) => {
+            let (rest, t) = type_expr(rest)?;
+            Ok((rest, Some(t)))
+        }
+        Err(_) => Ok((tokens, None)),
+    };
+}
+
+// 类型: 名字 int，泛型 List<int>，数组 []int [4]float，函数 fn(int): int，可选 ?int，
+// 或者直接写一个 struct { ... } / interface { ... }
+fn type_expr(tokens: &[Token]) -> Result<(&[Token], Type), ParseErr> {
+    if is_keyword(tokens, "struct") {
+        let (rest, obj) = struct_type(tokens)?;
+        let ParseObj::Struct(fields) = obj else {
+            unreachable!()
+        };
+        return Ok((rest, Type::Struct(fields)));
+    }
+    if is_keyword(tokens, "interface") {
+        let (rest, obj) = interface_type(tokens)?;
+        let ParseObj::Interface(methods) = obj else {
+            unreachable!()
+        };
+        return Ok((rest, Type::Interface(methods)));
+    }
+    if is_keyword(tokens, "fn") {
+        let rest = expect(&tokens[1..], TokenKind::Punct, "(")?;
+        let (rest, params) = comma_list(rest, ")", type_expr)?;
+        let (rest, ret) = return_type(rest)?;
+        return Ok((rest, Type::Fn(params, Box::new(ret))));
+    }
+    if let Ok(rest) = expect(tokens, TokenKind::Punct, "?") {
+        let (rest, inner) = type_expr(rest)?;
+        return Ok((rest, Type::Optional(Box::new(inner))));
+    }
+    if let Ok(rest) = expect(tokens, TokenKind::Punct, "[") {
+        let (rest, len) = match expect(rest, TokenKind::Punct, "]") {
+            Ok(rest) => (rest, None),
+            Err(_) => {
+                let (rest, len) = expr(rest)?;
+                (expect(rest, TokenKind::Punct, "]")?, Some(len))
             }
+        };
+        let (rest, elem) = type_expr(rest)?;
+        return Ok((rest, Type::Array(Box::new(len), Box::new(elem))));
+    }
+    let (tokens, name) = match ident(tokens) {
+        Ok((rest, ParseObj::Ident(name))) => (rest, name),
+        Ok(_) => unreachable!(),
+        Err(ParseErr::Unexpected(_, found, location)) => {
+            return Err(ParseErr::Unexpected("type".to_string(), found, location))
+        }
+        Err(err) => return Err(err),
+    };
+    // 类型的位置上没有比较运算，< 一定是类型参数的开始
+    if let Ok(rest) = expect(tokens, TokenKind::Op, "<") {
+        let (rest, args) = type_args(rest)?;
+        return Ok((rest, Type::Generic(name, args)));
+    }
+    return Ok((tokens, Type::Named(name)));
+}
+
+// < 后面逗号分隔的类型参数，一直到 >，至少要有一个
+fn type_args(tokens: &[Token]) -> Result<(&[Token], Vec<Type>), ParseErr> {
+    let mut tokens = tokens;
+    let mut args = Vec::new();
+    loop {
+        let (rest, arg) = type_expr(tokens)?;
+        args.push(arg);
+        if let Ok(rest) = expect(rest, TokenKind::Op, ">") {
+            return Ok((rest, args));
         }
-        return Ok((input, ParseObj::Keyword(word.clone())));
+        tokens = expect(rest, TokenKind::Punct, ",").map_err(|_| unexpected("`,` or `>`", rest))?;
+    }
+}
+
+// 表达式里的 [ 一定是数组字面量: [1, 2, 3] 或者 [0; 16]，数组类型只出现在类型的位置上
+fn array_lit(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Punct, "[")?;
+    if let Ok(rest) = expect(tokens, TokenKind::Punct, "]") {
+        return Ok((rest, ParseObj::ArrayLit(vec![])));
+    }
+    let (tokens, first) = expr(tokens)?;
+    if let Ok(rest) = expect(tokens, TokenKind::Punct, ";") {
+        let (rest, count) = expr(rest)?;
+        let rest = expect(rest, TokenKind::Punct, "]")?;
+        return Ok((
+            rest,
+            ParseObj::ArrayRepeat(Box::new(first), Box::new(count)),
+        ));
+    }
+    let mut elems = vec![first];
+    let tokens = match expect(tokens, TokenKind::Punct, ",") {
+        Ok(rest) => {
+            let (rest, more) = comma_list(rest, "]", expr)?;
+            elems.extend(more);
+            rest
+        }
+        Err(_) => expect(tokens, TokenKind::Punct, "]")
+            .map_err(|_| unexpected("`,`, `;` or `]`", tokens))?,
     };
+    return Ok((tokens, ParseObj::ArrayLit(elems)));
 }
 
-fn bool(input: String) -> ParseResult {
-    let _true = keyword("true".to_string());
-    let _false = keyword("false".to_string());
-    let (remains, bool_parsed) = any_of(vec![_true, _false])(input)?;
-    if let ParseObj::Keyword(b) = bool_parsed {
-        return Ok((remains, ParseObj::Bool(b == "true")));
-    } else {
+// struct { x: float, y: float }，字段和参数的写法一样
+fn struct_type(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Keyword, "struct")?;
+    let tokens = expect(tokens, TokenKind::Punct, "{")?;
+    let (tokens, fields) = comma_list(tokens, "}", param)?;
+    return Ok((tokens, ParseObj::Struct(fields)));
+}
+
+// Point { x: 1.0, y: 2.0 }，只写字段名 x 是 x: x 的简写
+fn struct_lit(tokens: &[Token]) -> TokenResult<'_> {
+    let (tokens, name) = ident(tokens)?;
+    let tokens = expect(tokens, TokenKind::Punct, "{")?;
+    return struct_lit_fields(tokens, name);
+}
+
+// { 后面的字段，一直到 }
+fn struct_lit_fields(tokens: &[Token], name: ParseObj) -> TokenResult<'_> {
+    let (tokens, fields) = comma_list(tokens, "}", field_init)?;
+    return Ok((tokens, ParseObj::StructLit(Box::new(name), fields)));
+}
+
+// { Circle(float), Rect { w: float, h: float }, Empty }，enum 关键字和名字已经吃掉了
+fn enum_body(tokens: &[Token]) -> TokenResult<'_> {
+    let tokens = expect(tokens, TokenKind::Punct, "{")?;
+    let (tokens, variants) = comma_list(tokens, "}", variant)?;
+    return Ok((tokens, ParseObj::Enum(variants)));
+}
+
+fn variant(tokens: &[Token]) -> Result<(&[Token], Variant), ParseErr> {
+    let (tokens, name) = ident(tokens)?;
+    let ParseObj::Ident(name) = name else {
         unreachable!()
+    };
+    if let Ok(rest) = expect(tokens, TokenKind::Punct, "(") {
+        let (rest, types) = comma_list(rest, ")", type_expr)?;
+        return Ok((rest, Variant::Tuple(name, types)));
+    }
+    if let Ok(rest) = expect(tokens, TokenKind::Punct, "{") {
+        let (rest, fields) = comma_list(rest, "}", param)?;
+        return Ok((rest, Variant::Struct(name, fields)));
     }
+    return Ok((tokens, Variant::Unit(name)));
 }
-fn expr(input: String) -> ParseResult {
-    // bool
-    // ident
-    // String
-    // int, uint, float
-    // fn_call
-    // fn_def
 
-    let parsers: Vec<fn(String) -> Result<(String, ParseObj), ParseErr>> = vec![bool, ident];
-    return any_of(parsers)(input);
+fn is_path(tokens: &[Token]) -> bool {
+    return tokens[0].kind == TokenKind::Ident
+        && tokens[1].kind == TokenKind::Punct
+        && tokens[1].text == "::";
+}
+
+// Shape::Circle
+fn path(tokens: &[Token]) -> TokenResult<'_> {
+    let enum_name = tokens[0].text.clone();
+    let tokens = expect(&tokens[1..], TokenKind::Punct, "::")?;
+    let (tokens, variant) = ident(tokens)?;
+    let ParseObj::Ident(variant) = variant else {
+        unreachable!()
+    };
+    return Ok((tokens, ParseObj::Path(enum_name, variant)));
+}
+
+fn field_init(tokens: &[Token]) -> Result<(&[Token], (ParseObj, ParseObj)), ParseErr> {
+    let (tokens, name) = ident(tokens)?;
+    return match expect(tokens, TokenKind::Punct, ":") {
+        Ok(rest) => {
+            let (rest, value) = expr(rest)?;
+            Ok((rest, (name, value)))
+        }
+        Err(_) => Ok((tokens, (name.clone(), name))),
+    };
 }
 
 #[test]
 fn test_parse_decl_bool() {
-    let decl_res = decl("a = false".to_string());
+    let decl_res = parse_str(decl, "let a = false".to_string());
     assert!(decl_res.is_ok());
 
-    // Box<Option<ParseObj>>：Box是一个指向堆内存的智能指针。它 可以有效地管理和引用堆内存上的数据。此处，Box是用来存储Option<ParseObj>类型的值。
+    // Box<Option<Type>>：Box是一个指向堆内存的智能指针。它 可以有效地管理和引用堆内存上的数据。此处，Box是用来存储Option<Type>类型的值。
 
     //  当你使用Box::new(None)创建一个新的boxed None值时，
-    // 你实际上是在堆上分配了一个Option<ParseObj>的空间，并初始化为None。这可能是因为你希望稍后将这个空位置填充为Some(ParseObj)。
-    let none: Box<Option<ParseObj>> = Box::new(None);
-    if let (_, ParseObj::Decl(name, none, be)) = decl_res.unwrap() {
+    // 你实际上是在堆上分配了一个Option<Type>的空间，并初始化为None。这可能是因为你希望稍后将这个空位置填充为Some(Type)。
+    let none: Box<Option<Type>> = Box::new(None);
+    if let (_, ParseObj::Decl(name, ty, be)) = decl_res.unwrap() {
         assert_eq!(name, "a");
+        assert_eq!(ty, none);
         assert_eq!(be, Box::new(ParseObj::Bool(false)));
     } else {
-        assert!(false);
+        panic!("expected a Decl");
     }
 }
 
 #[test]
 fn test_parse_decl_int() {
-    let decl_res = decl("a = -2".to_string());
+    let decl_res = parse_str(decl, "let a = -2".to_string());
     assert!(decl_res.is_ok());
-    let none: Box<Option<ParseObj>> = Box::new(None);
-    if let (_, ParseObj::Decl(name, none, be)) = decl_res.unwrap() {
+    let none: Box<Option<Type>> = Box::new(None);
+    if let (_, ParseObj::Decl(name, ty, be)) = decl_res.unwrap() {
         assert_eq!(name, "a");
+        assert_eq!(ty, none);
         assert_eq!(be, Box::new(ParseObj::Int(-2)));
     } else {
-        assert!(false);
+        panic!("expected a Decl");
+    }
+}
+
+#[test]
+fn test_parse_decl_literals() {
+    let cases = vec![
+        ("let f = 1_000", ParseObj::Int(1000)),
+        ("let f = 2.5e-3", ParseObj::Float(2.5e-3)),
+        ("let f = 10u", ParseObj::Uint(10)),
+        ("let f = \"hi\\n\"", ParseObj::Str("hi\n".to_string())),
+        ("let f = '\\u{263A}'", ParseObj::Char('☺')),
+    ];
+    for (src, expected) in cases {
+        assert_eq!(
+            parse_str(decl, src.to_string()).map(|(_, d)| d),
+            Ok(ParseObj::Decl(
+                "f".to_string(),
+                Box::new(None),
+                Box::new(expected)
+            ))
+        );
     }
+    assert_eq!(
+        parse_str(decl, format!("let f = {}", isize::MIN)).map(|(_, d)| d),
+        Ok(ParseObj::Decl(
+            "f".to_string(),
+            Box::new(None),
+            Box::new(ParseObj::Int(isize::MIN))
+        ))
+    );
+    assert_eq!(
+        parse_str(decl, "let f = 9223372036854775808".to_string()),
+        Err(ParseErr::Unexpected(
+            format!("integer literal in range {}..={}", isize::MIN, isize::MAX),
+            "9223372036854775808".to_string(),
+            8
+        ))
+    );
+}
+
+#[test]
+fn test_parse_decl_skips_comments() {
+    assert_eq!(
+        parse_str(
+            decl,
+            "let a /* type-less */ =\r\n  // value follows\r\n  true\r\nb = 1".to_string()
+        ),
+        Ok((
+            "b = 1".to_string(),
+            ParseObj::Decl(
+                "a".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Bool(true))
+            )
+        ))
+    );
+}
+
+#[test]
+fn test_doc_comments_attach_to_decl() {
+    let src = "/// The answer\n//// not a doc comment\n/// to everything\nlet answer = 42";
+    assert_eq!(
+        parse_str(decl, src.to_string()).map(|(_, d)| d),
+        Ok(ParseObj::Doc(
+            vec![" The answer".to_string(), " to everything".to_string()],
+            Box::new(ParseObj::Decl(
+                "answer".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Int(42))
+            ))
+        ))
+    );
+}
+
+#[test]
+fn test_parse_ident() {
+    for name in ["x1", "count2", "_tmp", "größe", "変数", "trueish"] {
+        assert_eq!(
+            parse_str(expr, format!("{} rest", name)),
+            Ok(("rest".to_string(), ParseObj::Ident(name.to_string())))
+        );
+    }
+    assert!(parse_str(ident, "1x".to_string()).is_err());
+}
+
+#[test]
+fn test_ident_rejects_keywords() {
+    for word in ["true", "false", "if", "for"] {
+        assert_eq!(
+            parse_str(ident, word.to_string()),
+            Err(ParseErr::Unexpected(
+                "identifier".to_string(),
+                format!("keyword `{}`", word),
+                0
+            ))
+        );
+    }
+    assert!(parse_str(decl, "let if = 1".to_string()).is_err());
+}
+
+// 测试里用来拼期望的语法树
+#[cfg(test)]
+fn binary(op: &str, lhs: ParseObj, rhs: ParseObj) -> ParseObj {
+    return ParseObj::Binary(op.to_string(), Box::new(lhs), Box::new(rhs));
+}
+
+#[cfg(test)]
+fn ident_obj(name: &str) -> ParseObj {
+    return ParseObj::Ident(name.to_string());
+}
+
+#[cfg(test)]
+fn named(name: &str) -> Type {
+    return Type::Named(name.to_string());
+}
+
+#[test]
+fn test_parse_operator_precedence() {
+    assert_eq!(
+        parse_str(expr, "1 + 2 * 3 == 7 || !done && x % 2 != 0".to_string()),
+        Ok((
+            "".to_string(),
+            binary(
+                "||",
+                binary(
+                    "==",
+                    binary(
+                        "+",
+                        ParseObj::Int(1),
+                        binary("*", ParseObj::Int(2), ParseObj::Int(3))
+                    ),
+                    ParseObj::Int(7)
+                ),
+                binary(
+                    "&&",
+                    ParseObj::Unary("!".to_string(), Box::new(ident_obj("done"))),
+                    binary(
+                        "!=",
+                        binary("%", ident_obj("x"), ParseObj::Int(2)),
+                        ParseObj::Int(0)
+                    )
+                )
+            )
+        ))
+    );
+}
+
+#[test]
+fn test_parse_operator_associativity_and_parens() {
+    assert_eq!(
+        parse_str(expr, "a - b - c".to_string()).map(|(_, e)| e),
+        Ok(binary(
+            "-",
+            binary("-", ident_obj("a"), ident_obj("b")),
+            ident_obj("c")
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "(a + -b) * -2".to_string()).map(|(_, e)| e),
+        Ok(binary(
+            "*",
+            binary(
+                "+",
+                ident_obj("a"),
+                ParseObj::Unary("-".to_string(), Box::new(ident_obj("b")))
+            ),
+            ParseObj::Int(-2)
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "a <= b - 1 < c".to_string()).map(|(_, e)| e),
+        Ok(binary(
+            "<",
+            binary(
+                "<=",
+                ident_obj("a"),
+                binary("-", ident_obj("b"), ParseObj::Int(1))
+            ),
+            ident_obj("c")
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "(a + b".to_string()),
+        Err(ParseErr::Unexpected(
+            "`)`".to_string(),
+            "nothing".to_string(),
+            6
+        ))
+    );
+    assert!(parse_str(expr, "a +".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_fn_call() {
+    let call = |callee: ParseObj, args: Vec<ParseObj>| ParseObj::FnCall(Box::new(callee), args);
+    assert_eq!(
+        parse_str(expr, "now()".to_string()).map(|(_, e)| e),
+        Ok(call(ident_obj("now"), vec![]))
+    );
+    assert_eq!(
+        parse_str(expr, "add(1, mul(x, 2) + 1,)".to_string()).map(|(_, e)| e),
+        Ok(call(
+            ident_obj("add"),
+            vec![
+                ParseObj::Int(1),
+                binary(
+                    "+",
+                    call(ident_obj("mul"), vec![ident_obj("x"), ParseObj::Int(2)]),
+                    ParseObj::Int(1)
+                )
+            ]
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "-make_adder(1)(2)".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Unary(
+            "-".to_string(),
+            Box::new(call(
+                call(ident_obj("make_adder"), vec![ParseObj::Int(1)]),
+                vec![ParseObj::Int(2)]
+            ))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "f(a b)".to_string()),
+        Err(ParseErr::Unexpected(
+            "`,` or `)`".to_string(),
+            "b".to_string(),
+            4
+        ))
+    );
+    assert!(parse_str(expr, "f(,)".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_fn_def() {
+    let params = vec![
+        (ident_obj("a"), named("int")),
+        (ident_obj("b"), named("int")),
+    ];
+    assert_eq!(
+        parse_str(
+            decl,
+            "let add = fn (a: int, b: int): int { a + b }".to_string()
+        )
+        .map(|(_, d)| d),
+        Ok(ParseObj::Decl(
+            "add".to_string(),
+            Box::new(None),
+            Box::new(ParseObj::Fn(
+                params.clone(),
+                Box::new(Some(named("int"))),
+                Box::new(ParseObj::Block(vec![binary(
+                    "+",
+                    ident_obj("a"),
+                    ident_obj("b")
+                )]))
+            ))
+        ))
+    );
+    assert_eq!(
+        parse_str(
+            decl,
+            "/// Adds.\nfn add(a: int, b: int,) { let c = a + b; c }".to_string()
+        )
+        .map(|(_, d)| d),
+        Ok(ParseObj::Doc(
+            vec![" Adds.".to_string()],
+            Box::new(ParseObj::Decl(
+                "add".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Fn(
+                    params,
+                    Box::new(None),
+                    Box::new(ParseObj::Block(vec![
+                        ParseObj::Stmt(Box::new(ParseObj::Decl(
+                            "c".to_string(),
+                            Box::new(None),
+                            Box::new(binary("+", ident_obj("a"), ident_obj("b")))
+                        ))),
+                        ident_obj("c")
+                    ]))
+                ))
+            ))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "fn () {}".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Fn(
+            vec![],
+            Box::new(None),
+            Box::new(ParseObj::Block(vec![]))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "fn (a) {}".to_string()),
+        Err(ParseErr::Unexpected("`:`".to_string(), ")".to_string(), 5))
+    );
+}
+
+#[test]
+fn test_parse_struct_type() {
+    assert_eq!(
+        parse_str(
+            decl,
+            "let Line = struct { from: Point, to: Point, meta: struct { id: int }, }".to_string()
+        )
+        .map(|(_, d)| d),
+        Ok(ParseObj::Decl(
+            "Line".to_string(),
+            Box::new(None),
+            Box::new(ParseObj::Struct(vec![
+                (ident_obj("from"), named("Point")),
+                (ident_obj("to"), named("Point")),
+                (
+                    ident_obj("meta"),
+                    Type::Struct(vec![(ident_obj("id"), named("int"))])
+                ),
+            ]))
+        ))
+    );
+    assert!(parse_str(expr, "struct { x }".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_struct_lit() {
+    let point = |x: ParseObj, y: ParseObj| {
+        ParseObj::StructLit(
+            Box::new(ident_obj("Point")),
+            vec![(ident_obj("x"), x), (ident_obj("y"), y)],
+        )
+    };
+    assert_eq!(
+        parse_str(expr, "Point { x: 1.0, y: 2.0 }".to_string()).map(|(_, e)| e),
+        Ok(point(ParseObj::Float(1.0), ParseObj::Float(2.0)))
+    );
+    assert_eq!(
+        parse_str(
+            expr,
+            "Line { from: Point { x, y }, to: Point { x: 0.5, y: -y }, }".to_string()
+        )
+        .map(|(_, e)| e),
+        Ok(ParseObj::StructLit(
+            Box::new(ident_obj("Line")),
+            vec![
+                (ident_obj("from"), point(ident_obj("x"), ident_obj("y"))),
+                (
+                    ident_obj("to"),
+                    point(
+                        ParseObj::Float(0.5),
+                        ParseObj::Unary("-".to_string(), Box::new(ident_obj("y")))
+                    )
+                ),
+            ]
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "Empty {}".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::StructLit(Box::new(ident_obj("Empty")), vec![]))
+    );
+}
+
+#[test]
+fn test_parse_array_type() {
+    assert_eq!(
+        parse_str(expr, "fn (xs: []int, m: [4][N]float) {}".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Fn(
+            vec![
+                (
+                    ident_obj("xs"),
+                    Type::Array(Box::new(None), Box::new(named("int")))
+                ),
+                (
+                    ident_obj("m"),
+                    Type::Array(
+                        Box::new(Some(ParseObj::Int(4))),
+                        Box::new(Type::Array(
+                            Box::new(Some(ident_obj("N"))),
+                            Box::new(named("float"))
+                        ))
+                    )
+                ),
+            ],
+            Box::new(None),
+            Box::new(ParseObj::Block(vec![]))
+        ))
+    );
+}
+
+#[test]
+fn test_parse_array_lit() {
+    assert_eq!(
+        parse_str(expr, "[1, 2, 3,]".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::ArrayLit(vec![
+            ParseObj::Int(1),
+            ParseObj::Int(2),
+            ParseObj::Int(3)
+        ]))
+    );
+    assert_eq!(
+        parse_str(expr, "[0; 16]".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::ArrayRepeat(
+            Box::new(ParseObj::Int(0)),
+            Box::new(ParseObj::Int(16))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "[[], [x]]".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::ArrayLit(vec![
+            ParseObj::ArrayLit(vec![]),
+            ParseObj::ArrayLit(vec![ident_obj("x")])
+        ]))
+    );
+    // 表达式里没有数组类型
+    assert_eq!(
+        parse_str(expr, "[]int".to_string()),
+        Ok(("int".to_string(), ParseObj::ArrayLit(vec![])))
+    );
+    assert_eq!(
+        parse_str(expr, "[1 2]".to_string()),
+        Err(ParseErr::Unexpected(
+            "`,`, `;` or `]`".to_string(),
+            "2".to_string(),
+            3
+        ))
+    );
+}
+
+#[cfg(test)]
+fn stmt_obj(obj: ParseObj) -> ParseObj {
+    return ParseObj::Stmt(Box::new(obj));
+}
+
+#[test]
+fn test_parse_block() {
+    let src = "{
+        let a = 1;
+        log(a);
+        { let b = a * 2; b };
+        fn twice(x: int) { x * 2 }
+        {}
+        twice(a)
+    }";
+    assert_eq!(
+        parse_str(expr, src.to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Decl(
+                "a".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Int(1))
+            )),
+            stmt_obj(ParseObj::FnCall(
+                Box::new(ident_obj("log")),
+                vec![ident_obj("a")]
+            )),
+            stmt_obj(ParseObj::Block(vec![
+                stmt_obj(ParseObj::Decl(
+                    "b".to_string(),
+                    Box::new(None),
+                    Box::new(binary("*", ident_obj("a"), ParseObj::Int(2)))
+                )),
+                ident_obj("b"),
+            ])),
+            stmt_obj(ParseObj::Decl(
+                "twice".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Fn(
+                    vec![(ident_obj("x"), named("int"))],
+                    Box::new(None),
+                    Box::new(ParseObj::Block(vec![binary(
+                        "*",
+                        ident_obj("x"),
+                        ParseObj::Int(2)
+                    )]))
+                ))
+            )),
+            stmt_obj(ParseObj::Block(vec![])),
+            ParseObj::FnCall(Box::new(ident_obj("twice")), vec![ident_obj("a")]),
+        ]))
+    );
+    // 语句开头的块到 } 就结束，后面的 ( [ - 是下一个语句
+    assert_eq!(
+        parse_str(expr, "{ {} (a) }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Block(vec![])),
+            ident_obj("a")
+        ]))
+    );
+    assert_eq!(
+        parse_str(expr, "{ { f() } [1] }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Block(vec![ParseObj::FnCall(
+                Box::new(ident_obj("f")),
+                vec![]
+            )])),
+            ParseObj::ArrayLit(vec![ParseObj::Int(1)])
+        ]))
+    );
+    assert_eq!(
+        parse_str(expr, "{ {} -x }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Block(vec![])),
+            ParseObj::Unary("-".to_string(), Box::new(ident_obj("x")))
+        ]))
+    );
+}
+
+#[test]
+fn test_parse_block_value_and_errors() {
+    assert_eq!(
+        parse_str(decl, "let x = { let y = 2; y + 1 }".to_string()).map(|(_, d)| d),
+        Ok(ParseObj::Decl(
+            "x".to_string(),
+            Box::new(None),
+            Box::new(ParseObj::Block(vec![
+                stmt_obj(ParseObj::Decl(
+                    "y".to_string(),
+                    Box::new(None),
+                    Box::new(ParseObj::Int(2))
+                )),
+                binary("+", ident_obj("y"), ParseObj::Int(1)),
+            ]))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "{ a; }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![stmt_obj(ident_obj("a"))]))
+    );
+    assert_eq!(
+        parse_str(expr, "{ a b }".to_string()),
+        Err(ParseErr::Unexpected(
+            "`;` or `}`".to_string(),
+            "b".to_string(),
+            4
+        ))
+    );
+    assert!(parse_str(expr, "{ a;".to_string()).is_err());
+    assert!(parse_str(expr, "{ a = 1 b = 2 }".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_if_else_chain() {
+    let if_obj = |cond: ParseObj, then: Vec<ParseObj>, otherwise: Option<ParseObj>| {
+        ParseObj::If(
+            Box::new(cond),
+            Box::new(ParseObj::Block(then)),
+            Box::new(otherwise),
+        )
+    };
+    assert_eq!(
+        parse_str(
+            decl,
+            "let x = if a < b { 1 } else if ready { 2 } else { 3 }".to_string()
+        )
+        .map(|(_, d)| d),
+        Ok(ParseObj::Decl(
+            "x".to_string(),
+            Box::new(None),
+            Box::new(if_obj(
+                binary("<", ident_obj("a"), ident_obj("b")),
+                vec![ParseObj::Int(1)],
+                Some(if_obj(
+                    ident_obj("ready"),
+                    vec![ParseObj::Int(2)],
+                    Some(ParseObj::Block(vec![ParseObj::Int(3)]))
+                ))
+            ))
+        ))
+    );
+    // 条件里的 p { 不是 struct 字面量，括号里可以写
+    assert_eq!(
+        parse_str(expr, "{ if p { go() } if (p == P {}) {} }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(if_obj(
+                ident_obj("p"),
+                vec![ParseObj::FnCall(Box::new(ident_obj("go")), vec![])],
+                None
+            )),
+            stmt_obj(if_obj(
+                binary(
+                    "==",
+                    ident_obj("p"),
+                    ParseObj::StructLit(Box::new(ident_obj("P")), vec![])
+                ),
+                vec![],
+                None
+            )),
+        ]))
+    );
+}
+
+#[test]
+fn test_parse_if_value_needs_else() {
+    assert_eq!(
+        parse_str(decl, "let x = if a { 1 }".to_string()),
+        Err(ParseErr::Unexpected(
+            "`else` branch for if used as a value".to_string(),
+            "nothing".to_string(),
+            18
+        ))
+    );
+    assert!(parse_str(decl, "let x = if a { 1 } else if b { 2 }".to_string()).is_err());
+    assert!(parse_str(expr, "1 + if a { 1 }".to_string()).is_err());
+    // 块最后没有 else 的 if 只是语句，块没有值
+    assert_eq!(
+        parse_str(decl, "let x = { if a { 1 } }".to_string()).map(|(_, d)| d),
+        Ok(ParseObj::Decl(
+            "x".to_string(),
+            Box::new(None),
+            Box::new(ParseObj::Block(vec![stmt_obj(ParseObj::If(
+                Box::new(ident_obj("a")),
+                Box::new(ParseObj::Block(vec![ParseObj::Int(1)])),
+                Box::new(None)
+            ))]))
+        ))
+    );
+    let src = "fn main() {
+    if ready { start(); }
+    while x { if done { break; } }
+    match x { _ => { if a { 1 } else if b { 2 } } }
+}";
+    assert!(parse_program(src.to_string()).is_ok());
+}
+
+#[test]
+fn test_parse_for_c() {
+    assert_eq!(
+        parse_str(
+            expr,
+            "for var i = 0; i < 10; i = i + 1 { sum = sum + i; }".to_string()
+        )
+        .map(|(_, e)| e),
+        Ok(ParseObj::ForC(
+            Box::new(ParseObj::Var(
+                "i".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Int(0))
+            )),
+            Box::new(binary("<", ident_obj("i"), ParseObj::Int(10))),
+            Box::new(ParseObj::Assign(
+                Box::new(ident_obj("i")),
+                Box::new(binary("+", ident_obj("i"), ParseObj::Int(1)))
+            )),
+            Box::new(ParseObj::Block(vec![stmt_obj(ParseObj::Assign(
+                Box::new(ident_obj("sum")),
+                Box::new(binary("+", ident_obj("sum"), ident_obj("i")))
+            ))]))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "for ;; { }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::ForC(
+            Box::new(ParseObj::Empty),
+            Box::new(ParseObj::Empty),
+            Box::new(ParseObj::Empty),
+            Box::new(ParseObj::Block(vec![]))
+        ))
+    );
+    // step 里的 next { 不是 struct 字面量
+    assert_eq!(
+        parse_str(expr, "for ; ok; p = next { step() }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::ForC(
+            Box::new(ParseObj::Empty),
+            Box::new(ident_obj("ok")),
+            Box::new(ParseObj::Assign(
+                Box::new(ident_obj("p")),
+                Box::new(ident_obj("next"))
+            )),
+            Box::new(ParseObj::Block(vec![ParseObj::FnCall(
+                Box::new(ident_obj("step")),
+                vec![]
+            )]))
+        ))
+    );
+    assert!(parse_str(expr, "for var i = 0; i < 10 { }".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_foreach_and_ranges() {
+    assert_eq!(
+        parse_str(expr, "for i in 0..n + 1 { total = total + i; }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::ForEach(
+            Box::new(ident_obj("i")),
+            Box::new(binary(
+                "..",
+                ParseObj::Int(0),
+                binary("+", ident_obj("n"), ParseObj::Int(1))
+            )),
+            Box::new(ParseObj::Block(vec![stmt_obj(ParseObj::Assign(
+                Box::new(ident_obj("total")),
+                Box::new(binary("+", ident_obj("total"), ident_obj("i")))
+            ))]))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "for p in points { draw(p) }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::ForEach(
+            Box::new(ident_obj("p")),
+            Box::new(ident_obj("points")),
+            Box::new(ParseObj::Block(vec![ParseObj::FnCall(
+                Box::new(ident_obj("draw")),
+                vec![ident_obj("p")]
+            )]))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "1..=10".to_string()).map(|(_, e)| e),
+        Ok(binary("..=", ParseObj::Int(1), ParseObj::Int(10)))
+    );
+}
+
+#[test]
+fn test_parse_while_loop_break_continue() {
+    let src = "'outer: loop {
+        while busy() {
+            if skip { continue; }
+            if done { break 'outer; }
+            break
+        }
+    }";
+    let if_then = |cond: &str, stmt: ParseObj| {
+        stmt_obj(ParseObj::If(
+            Box::new(ident_obj(cond)),
+            Box::new(ParseObj::Block(vec![stmt_obj(stmt)])),
+            Box::new(None),
+        ))
+    };
+    assert_eq!(
+        parse_str(expr, src.to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Labeled(
+            "outer".to_string(),
+            Box::new(ParseObj::Loop(Box::new(ParseObj::Block(vec![
+                ParseObj::While(
+                    Box::new(ParseObj::FnCall(Box::new(ident_obj("busy")), vec![])),
+                    Box::new(ParseObj::Block(vec![
+                        if_then("skip", ParseObj::Continue(None, 60)),
+                        if_then(
+                            "done",
+                            ParseObj::Break(Some("outer".to_string()), Box::new(None), 94)
+                        ),
+                        ParseObj::Break(None, Box::new(None), 122),
+                    ]))
+                )
+            ]))))
+        ))
+    );
+    assert!(parse_str(expr, "'outer: if a {}".to_string()).is_err());
+    // 语句开头的循环也到 } 就结束
+    assert_eq!(
+        parse_str(expr, "{ for i in xs {} -x }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::ForEach(
+                Box::new(ident_obj("i")),
+                Box::new(ident_obj("xs")),
+                Box::new(ParseObj::Block(vec![]))
+            )),
+            ParseObj::Unary("-".to_string(), Box::new(ident_obj("x")))
+        ]))
+    );
+    assert_eq!(
+        parse_str(expr, "{ loop { break } (f)(1) }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Loop(Box::new(ParseObj::Block(vec![
+                ParseObj::Break(None, Box::new(None), 9)
+            ])))),
+            ParseObj::FnCall(Box::new(ident_obj("f")), vec![ParseObj::Int(1)])
+        ]))
+    );
+    assert_eq!(
+        parse_str(expr, "{ while a { } [1, 2] }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::While(
+                Box::new(ident_obj("a")),
+                Box::new(ParseObj::Block(vec![]))
+            )),
+            ParseObj::ArrayLit(vec![ParseObj::Int(1), ParseObj::Int(2)])
+        ]))
+    );
+    assert_eq!(
+        parse_str(expr, "{ 'outer: loop {} (a) }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Labeled(
+                "outer".to_string(),
+                Box::new(ParseObj::Loop(Box::new(ParseObj::Block(vec![]))))
+            )),
+            ident_obj("a")
+        ]))
+    );
+}
+
+#[test]
+fn test_parse_interface_and_impl() {
+    let sig = |params: Vec<(ParseObj, Type)>, ret: Option<Type>| {
+        ParseObj::Fn(params, Box::new(ret), Box::new(ParseObj::Empty))
+    };
+    assert_eq!(
+        parse_str(
+            decl,
+            "let Shape = interface { area(): float, scale(by: float), }".to_string()
+        )
+        .map(|(_, d)| d),
+        Ok(ParseObj::Decl(
+            "Shape".to_string(),
+            Box::new(None),
+            Box::new(ParseObj::Interface(vec![
+                (ident_obj("area"), sig(vec![], Some(named("float")))),
+                (
+                    ident_obj("scale"),
+                    sig(vec![(ident_obj("by"), named("float"))], None)
+                ),
+            ]))
+        ))
+    );
+    let src = "{
+        impl Shape for Circle {
+            /// Area of the circle.
+            fn area(r: float): float { 3.14 * r * r }
+            fn scale(r: float, by: float) {}
+        }
+        0
+    }";
+    let (_, parsed) = parse_str(expr, src.to_string()).unwrap();
+    let ParseObj::Block(items) = parsed else {
+        panic!("expected a Block");
+    };
+    let ParseObj::Stmt(item) = &items[0] else {
+        panic!("expected a Stmt");
+    };
+    let ParseObj::Impl(interface, target, methods) = &**item else {
+        panic!("expected an Impl");
+    };
+    assert_eq!(**interface, ident_obj("Shape"));
+    assert_eq!(**target, named("Circle"));
+    assert_eq!(methods.len(), 2);
+    assert!(
+        matches!(&methods[0], ParseObj::Doc(docs, _) if docs == &vec![" Area of the circle.".to_string()])
+    );
+    assert!(
+        matches!(&methods[1], ParseObj::Decl(name, _, f) if name == "scale" && matches!(**f, ParseObj::Fn(_, _, _)))
+    );
+    assert_eq!(
+        parse_str(stmt, "impl Shape for Circle { x = 1 }".to_string()),
+        Err(ParseErr::Unexpected(
+            "method `fn name(...) { }` or `}`".to_string(),
+            "x".to_string(),
+            24
+        ))
+    );
+    assert!(parse_str(expr, "interface { area: float }".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_program() {
+    let src = "/// Entry.
+fn main() { run(limit) }
+let limit: int = 10;
+impl Shape for Circle {}
+let answer = 42";
+    assert_eq!(
+        parse_program(src.to_string()),
+        Ok(ParseObj::Program(vec![
+            ParseObj::Doc(
+                vec![" Entry.".to_string()],
+                Box::new(ParseObj::Decl(
+                    "main".to_string(),
+                    Box::new(None),
+                    Box::new(ParseObj::Fn(
+                        vec![],
+                        Box::new(None),
+                        Box::new(ParseObj::Block(vec![ParseObj::FnCall(
+                            Box::new(ident_obj("run")),
+                            vec![ident_obj("limit")]
+                        )]))
+                    ))
+                ))
+            ),
+            ParseObj::Decl(
+                "limit".to_string(),
+                Box::new(Some(named("int"))),
+                Box::new(ParseObj::Int(10))
+            ),
+            ParseObj::Impl(
+                Box::new(ident_obj("Shape")),
+                Box::new(named("Circle")),
+                vec![]
+            ),
+            ParseObj::Decl(
+                "answer".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Int(42))
+            ),
+        ]))
+    );
+    assert_eq!(
+        parse_program("  // nothing here\n".to_string()),
+        Ok(ParseObj::Program(vec![]))
+    );
+}
+
+#[test]
+fn test_parse_documented_impl() {
+    let documented = ParseObj::Doc(
+        vec![" Circles are shapes.".to_string()],
+        Box::new(ParseObj::Impl(
+            Box::new(ident_obj("Shape")),
+            Box::new(named("Circle")),
+            vec![],
+        )),
+    );
+    assert_eq!(
+        parse_program("/// Circles are shapes.\nimpl Shape for Circle {}".to_string()),
+        Ok(ParseObj::Program(vec![documented.clone()]))
+    );
+    assert_eq!(
+        parse_str(
+            expr,
+            "{ /// Circles are shapes.\nimpl Shape for Circle {} 0 }".to_string()
+        )
+        .map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(documented),
+            ParseObj::Int(0)
+        ]))
+    );
+}
+
+#[test]
+fn test_parse_program_ignores_stray_doc_comments() {
+    let src = "let P = struct {
+    /// x coordinate
+    x: int,
+};
+enum E {
+    /// nothing
+    A,
+}
+/// trailing";
+    assert_eq!(
+        parse_program(src.to_string()).map(|p| match p {
+            ParseObj::Program(items) => items.len(),
+            _ => 0,
+        }),
+        Ok(2)
+    );
+    assert_eq!(
+        parse_str(expr, "{ /// note\n f() }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![ParseObj::FnCall(
+            Box::new(ident_obj("f")),
+            vec![]
+        )]))
+    );
+}
+
+#[test]
+fn test_parse_program_rejects_trailing_input() {
+    assert_eq!(
+        parse_program("let a = 1 let b = 2".to_string()),
+        Err(ParseErr::Unexpected(
+            "`;` or end of file".to_string(),
+            "let".to_string(),
+            10
+        ))
+    );
+    assert_eq!(
+        parse_program("let a = 1;\nb = 2".to_string()),
+        Err(ParseErr::Unexpected(
+            "declaration or `impl`".to_string(),
+            "b".to_string(),
+            11
+        ))
+    );
+    assert!(parse_program("let a = \"open".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_type_annotations() {
+    let decl_ty = |src: &str| match parse_str(decl, src.to_string()) {
+        Ok((_, ParseObj::Decl(_, ty, _))) => Ok(*ty),
+        Ok((_, other)) => panic!("expected a Decl, got {:?}", other),
+        Err(err) => Err(err),
+    };
+    assert_eq!(
+        decl_ty("let xs: []int = []"),
+        Ok(Some(Type::Array(Box::new(None), Box::new(named("int")))))
+    );
+    assert_eq!(
+        decl_ty("let f: fn(int, ?string): int = g"),
+        Ok(Some(Type::Fn(
+            vec![named("int"), Type::Optional(Box::new(named("string")))],
+            Box::new(Some(named("int")))
+        )))
+    );
+    assert_eq!(
+        decl_ty("let cb: ?fn() = none"),
+        Ok(Some(Type::Optional(Box::new(Type::Fn(
+            vec![],
+            Box::new(None)
+        )))))
+    );
+    assert_eq!(
+        decl_ty("let m: Map<string, List<[]int>> = empty"),
+        Ok(Some(Type::Generic(
+            "Map".to_string(),
+            vec![
+                named("string"),
+                Type::Generic(
+                    "List".to_string(),
+                    vec![Type::Array(Box::new(None), Box::new(named("int")))]
+                )
+            ]
+        )))
+    );
+    assert_eq!(
+        decl_ty("let a: true = false"),
+        Err(ParseErr::Unexpected(
+            "type".to_string(),
+            "keyword `true`".to_string(),
+            7
+        ))
+    );
+    assert_eq!(
+        decl_ty("let a: 1 + 2 = 3"),
+        Err(ParseErr::Unexpected("type".to_string(), "1".to_string(), 7))
+    );
+    assert_eq!(
+        decl_ty("let m: Map<string int> = empty"),
+        Err(ParseErr::Unexpected(
+            "`,` or `>`".to_string(),
+            "int".to_string(),
+            18
+        ))
+    );
+    assert!(decl_ty("let l: List<> = empty").is_err());
+}
+
+#[test]
+fn test_parse_postfix() {
+    let field = |obj: ParseObj, name: &str| ParseObj::Field(Box::new(obj), name.to_string());
+    let index = |obj: ParseObj, i: ParseObj| ParseObj::Index(Box::new(obj), Box::new(i));
+    let method = |obj: ParseObj, name: &str, args: Vec<ParseObj>| {
+        ParseObj::MethodCall(Box::new(obj), name.to_string(), args)
+    };
+    assert_eq!(
+        parse_str(expr, "a.b[c].d()".to_string()).map(|(_, e)| e),
+        Ok(method(
+            index(field(ident_obj("a"), "b"), ident_obj("c")),
+            "d",
+            vec![]
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "-p.x + list[i + 1] * s.len()".to_string()).map(|(_, e)| e),
+        Ok(binary(
+            "+",
+            ParseObj::Unary("-".to_string(), Box::new(field(ident_obj("p"), "x"))),
+            binary(
+                "*",
+                index(
+                    ident_obj("list"),
+                    binary("+", ident_obj("i"), ParseObj::Int(1))
+                ),
+                method(ident_obj("s"), "len", vec![])
+            )
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "(obj.handler)(1).items[0][1]".to_string()).map(|(_, e)| e),
+        Ok(index(
+            index(
+                field(
+                    ParseObj::FnCall(
+                        Box::new(field(ident_obj("obj"), "handler")),
+                        vec![ParseObj::Int(1)]
+                    ),
+                    "items"
+                ),
+                ParseObj::Int(0)
+            ),
+            ParseObj::Int(1)
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "-2.5.floor()".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Unary(
+            "-".to_string(),
+            Box::new(method(ParseObj::Float(2.5), "floor", vec![]))
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "1.max(2) + 0x10.count".to_string()).map(|(_, e)| e),
+        Ok(binary(
+            "+",
+            method(ParseObj::Int(1), "max", vec![ParseObj::Int(2)]),
+            field(ParseObj::Int(16), "count")
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "p.(x)".to_string()),
+        Err(ParseErr::Unexpected(
+            "field or method name after `.`".to_string(),
+            "(".to_string(),
+            2
+        ))
+    );
+    assert!(parse_str(expr, "xs[1".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_assignment() {
+    let src = "{
+        let p: Point = origin();
+        var count = 0;
+        count = 5;
+        count += 1;
+        p.x -= 2.5;
+        grid[i][j] *= -1;
+        total %= n
+    }";
+    let assign_to = |op: &str, place: ParseObj, value: ParseObj| {
+        ParseObj::CompoundAssign(op.to_string(), Box::new(place), Box::new(value))
+    };
+    let index = |obj: ParseObj, i: &str| ParseObj::Index(Box::new(obj), Box::new(ident_obj(i)));
+    assert_eq!(
+        parse_str(expr, src.to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Decl(
+                "p".to_string(),
+                Box::new(Some(named("Point"))),
+                Box::new(ParseObj::FnCall(Box::new(ident_obj("origin")), vec![]))
+            )),
+            stmt_obj(ParseObj::Var(
+                "count".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Int(0))
+            )),
+            stmt_obj(ParseObj::Assign(
+                Box::new(ident_obj("count")),
+                Box::new(ParseObj::Int(5))
+            )),
+            stmt_obj(assign_to("+", ident_obj("count"), ParseObj::Int(1))),
+            stmt_obj(assign_to(
+                "-",
+                ParseObj::Field(Box::new(ident_obj("p")), "x".to_string()),
+                ParseObj::Float(2.5)
+            )),
+            stmt_obj(assign_to(
+                "*",
+                index(index(ident_obj("grid"), "i"), "j"),
+                ParseObj::Int(-1)
+            )),
+            stmt_obj(assign_to("%", ident_obj("total"), ident_obj("n"))),
+        ]))
+    );
+    assert_eq!(
+        parse_str(stmt, "a + b = 1".to_string()),
+        Err(ParseErr::Unexpected(
+            "variable, field or index before `=`".to_string(),
+            "expression".to_string(),
+            0
+        ))
+    );
+    assert_eq!(
+        parse_str(stmt, "f() += 1".to_string()),
+        Err(ParseErr::Unexpected(
+            "variable, field or index before `+=`".to_string(),
+            "expression".to_string(),
+            0
+        ))
+    );
+    assert!(parse_str(decl, "x = 1".to_string()).is_err());
+    assert!(parse_str(decl, "var = 1".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_return_break_continue() {
+    let src = "fn find(xs: []int, target: int): int {
+        var i = 0;
+        'search: loop {
+            for x in xs {
+                if x == target { break 'search i }
+                if x < 0 { continue 'search; }
+                i += 1;
+            }
+            break;
+        }
+        return -1
+    }";
+    let program = parse_program(src.to_string()).unwrap();
+    let ParseObj::Program(items) = program else {
+        panic!("expected a Program");
+    };
+    let ParseObj::Decl(_, _, f) = &items[0] else {
+        panic!("expected a Decl");
+    };
+    let ParseObj::Fn(_, _, body) = &**f else {
+        panic!("expected a Fn");
+    };
+    let ParseObj::Block(stmts) = &**body else {
+        panic!("expected a Block");
+    };
+    assert_eq!(
+        stmts[2],
+        ParseObj::Return(Box::new(Some(ParseObj::Int(-1))))
+    );
+    let ParseObj::Stmt(labeled) = &stmts[1] else {
+        panic!("expected a Stmt");
+    };
+    let ParseObj::Labeled(label, outer) = &**labeled else {
+        panic!("expected a Labeled loop");
+    };
+    assert_eq!(label, "search");
+    let ParseObj::Loop(outer_body) = &**outer else {
+        panic!("expected a Loop");
+    };
+    assert_eq!(
+        **outer_body,
+        ParseObj::Block(vec![
+            stmt_obj(ParseObj::ForEach(
+                Box::new(ident_obj("x")),
+                Box::new(ident_obj("xs")),
+                Box::new(ParseObj::Block(vec![
+                    stmt_obj(ParseObj::If(
+                        Box::new(binary("==", ident_obj("x"), ident_obj("target"))),
+                        Box::new(ParseObj::Block(vec![ParseObj::Break(
+                            Some("search".to_string()),
+                            Box::new(Some(ident_obj("i"))),
+                            141
+                        )])),
+                        Box::new(None)
+                    )),
+                    stmt_obj(ParseObj::If(
+                        Box::new(binary("<", ident_obj("x"), ParseObj::Int(0))),
+                        Box::new(ParseObj::Block(vec![stmt_obj(ParseObj::Continue(
+                            Some("search".to_string()),
+                            186
+                        ))])),
+                        Box::new(None)
+                    )),
+                    stmt_obj(ParseObj::CompoundAssign(
+                        "+".to_string(),
+                        Box::new(ident_obj("i")),
+                        Box::new(ParseObj::Int(1))
+                    )),
+                ]))
+            )),
+            stmt_obj(ParseObj::Break(None, Box::new(None), 256)),
+        ])
+    );
+    assert_eq!(
+        parse_str(expr, "fn () { return }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Fn(
+            vec![],
+            Box::new(None),
+            Box::new(ParseObj::Block(vec![ParseObj::Return(Box::new(None))]))
+        ))
+    );
+}
+
+#[test]
+fn test_break_continue_outside_loop() {
+    assert_eq!(
+        parse_str(expr, "{ if done { break } }".to_string()),
+        Err(ParseErr::Unexpected(
+            "`break` inside a loop".to_string(),
+            "`break` outside of any loop".to_string(),
+            12
+        ))
+    );
+    // 函数体里的 continue 不属于外面的循环
+    assert_eq!(
+        parse_str(expr, "loop { let f = fn () { continue }; }".to_string()),
+        Err(ParseErr::Unexpected(
+            "`continue` inside a loop".to_string(),
+            "`continue` outside of any loop".to_string(),
+            23
+        ))
+    );
+    assert!(parse_str(expr, "while a { b(".to_string()).is_err());
+    assert!(parse_str(stmt, "break".to_string()).is_err());
+    assert!(parse_str(expr, "while a { fn f() {} break }".to_string()).is_ok());
+    // 类型里的循环不影响外面
+    assert_eq!(
+        parse_str(
+            expr,
+            "{ impl I for [loop { break }]int {} break }".to_string()
+        ),
+        Err(ParseErr::Unexpected(
+            "`break` inside a loop".to_string(),
+            "`break` outside of any loop".to_string(),
+            36
+        ))
+    );
+    assert_eq!(
+        parse_program(
+            "fn f(x: [loop { break 1 }]int) { while a { continue } g(); break }".to_string()
+        ),
+        Err(ParseErr::Unexpected(
+            "`break` inside a loop".to_string(),
+            "`break` outside of any loop".to_string(),
+            59
+        ))
+    );
+}
+
+#[test]
+fn test_parse_match() {
+    let src = "match shape {
+        Point { x: 0, y } => y,
+        Circle { r, .. } if r > 10.0 => { big(r) }
+        [first, .., -1] => first,
+        'a'..='z' => 1,
+        0..10 => return,
+        \"none\" => false,
+        _ => 0,
+    }";
+    let arm = |pat: Pattern, guard: Option<ParseObj>, body: ParseObj| {
+        ParseObj::Arm(Box::new(pat), Box::new(guard), Box::new(body))
+    };
+    let lit = |obj: ParseObj| Pattern::Literal(Box::new(obj));
+    assert_eq!(
+        parse_str(expr, src.to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Match(
+            Box::new(ident_obj("shape")),
+            vec![
+                arm(
+                    Pattern::Struct(
+                        Box::new(ident_obj("Point")),
+                        vec![
+                            ("x".to_string(), lit(ParseObj::Int(0))),
+                            ("y".to_string(), Pattern::Binding("y".to_string())),
+                        ],
+                        false
+                    ),
+                    None,
+                    ident_obj("y")
+                ),
+                arm(
+                    Pattern::Struct(
+                        Box::new(ident_obj("Circle")),
+                        vec![("r".to_string(), Pattern::Binding("r".to_string()))],
+                        true
+                    ),
+                    Some(binary(">", ident_obj("r"), ParseObj::Float(10.0))),
+                    ParseObj::Block(vec![ParseObj::FnCall(
+                        Box::new(ident_obj("big")),
+                        vec![ident_obj("r")]
+                    )])
+                ),
+                arm(
+                    Pattern::Array(vec![
+                        Pattern::Binding("first".to_string()),
+                        Pattern::Rest,
+                        lit(ParseObj::Int(-1)),
+                    ]),
+                    None,
+                    ident_obj("first")
+                ),
+                arm(
+                    Pattern::Range(
+                        Box::new(ParseObj::Char('a')),
+                        Box::new(ParseObj::Char('z')),
+                        true
+                    ),
+                    None,
+                    ParseObj::Int(1)
+                ),
+                arm(
+                    Pattern::Range(
+                        Box::new(ParseObj::Int(0)),
+                        Box::new(ParseObj::Int(10)),
+                        false
+                    ),
+                    None,
+                    ParseObj::Return(Box::new(None))
+                ),
+                arm(
+                    lit(ParseObj::Str("none".to_string())),
+                    None,
+                    ParseObj::Bool(false)
+                ),
+                arm(Pattern::Wildcard, None, ParseObj::Int(0)),
+            ]
+        ))
+    );
+}
+
+#[test]
+fn test_parse_match_errors() {
+    assert_eq!(
+        parse_str(expr, "match x { 1 => a 2 => b }".to_string()),
+        Err(ParseErr::Unexpected(
+            "`,` or `}`".to_string(),
+            "2".to_string(),
+            17
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "match x { (a) => a }".to_string()),
+        Err(ParseErr::Unexpected(
+            "pattern".to_string(),
+            "(".to_string(),
+            10
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "match p { P { .., x } => x }".to_string()),
+        Err(ParseErr::Unexpected(
+            "`}` after `..`".to_string(),
+            ",".to_string(),
+            16
+        ))
+    );
+    // match 作为语句不用写 ;
+    assert_eq!(
+        parse_str(expr, "{ match x {} y }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Match(Box::new(ident_obj("x")), vec![])),
+            ident_obj("y"),
+        ]))
+    );
+    assert!(parse_str(expr, "match x { 1..y => a }".to_string()).is_err());
+    // 语句开头的 match 也到 } 就结束
+    assert_eq!(
+        parse_str(expr, "{ match x {} (a) }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Match(Box::new(ident_obj("x")), vec![])),
+            ident_obj("a")
+        ]))
+    );
+    assert_eq!(
+        parse_str(expr, "{ match x { _ => f() } [a] }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Block(vec![
+            stmt_obj(ParseObj::Match(
+                Box::new(ident_obj("x")),
+                vec![ParseObj::Arm(
+                    Box::new(Pattern::Wildcard),
+                    Box::new(None),
+                    Box::new(ParseObj::FnCall(Box::new(ident_obj("f")), vec![]))
+                )]
+            )),
+            ParseObj::ArrayLit(vec![ident_obj("a")])
+        ]))
+    );
+}
+
+#[test]
+fn test_parse_enum_decl() {
+    let src = "/// Shapes we can draw.
+enum Shape { Circle(float), Rect { w: float, h: float }, Empty, }
+let Color = enum { Rgb(int, int, int), Named(string) };";
+    assert_eq!(
+        parse_program(src.to_string()),
+        Ok(ParseObj::Program(vec![
+            ParseObj::Doc(
+                vec![" Shapes we can draw.".to_string()],
+                Box::new(ParseObj::Decl(
+                    "Shape".to_string(),
+                    Box::new(None),
+                    Box::new(ParseObj::Enum(vec![
+                        Variant::Tuple("Circle".to_string(), vec![named("float")]),
+                        Variant::Struct(
+                            "Rect".to_string(),
+                            vec![
+                                (ident_obj("w"), named("float")),
+                                (ident_obj("h"), named("float")),
+                            ]
+                        ),
+                        Variant::Unit("Empty".to_string()),
+                    ]))
+                ))
+            ),
+            ParseObj::Decl(
+                "Color".to_string(),
+                Box::new(None),
+                Box::new(ParseObj::Enum(vec![
+                    Variant::Tuple(
+                        "Rgb".to_string(),
+                        vec![named("int"), named("int"), named("int")]
+                    ),
+                    Variant::Tuple("Named".to_string(), vec![named("string")]),
+                ]))
+            ),
+        ]))
+    );
+    assert_eq!(
+        parse_program("enum E { A B }".to_string()),
+        Err(ParseErr::Unexpected(
+            "`,` or `}`".to_string(),
+            "B".to_string(),
+            11
+        ))
+    );
+}
+
+#[test]
+fn test_parse_enum_construction_and_match() {
+    let path = |e: &str, v: &str| ParseObj::Path(e.to_string(), v.to_string());
+    assert_eq!(
+        parse_str(expr, "Shape::Circle(1.5)".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::FnCall(
+            Box::new(path("Shape", "Circle")),
+            vec![ParseObj::Float(1.5)]
+        ))
+    );
+    assert_eq!(
+        parse_str(expr, "Shape::Rect { w: 2.0, h }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::StructLit(
+            Box::new(path("Shape", "Rect")),
+            vec![
+                (ident_obj("w"), ParseObj::Float(2.0)),
+                (ident_obj("h"), ident_obj("h")),
+            ]
+        ))
+    );
+    // 条件里的 Path 后面的 { 是 if 的块
+    assert_eq!(
+        parse_str(expr, "if s == Shape::Empty { 0 } else { 1 }".to_string()).map(|(_, e)| e),
+        Ok(ParseObj::If(
+            Box::new(binary("==", ident_obj("s"), path("Shape", "Empty"))),
+            Box::new(ParseObj::Block(vec![ParseObj::Int(0)])),
+            Box::new(Some(ParseObj::Block(vec![ParseObj::Int(1)])))
+        ))
+    );
+    let src = "match s {
+        Shape::Circle(r) => r * r,
+        Shape::Rect { w, h: 0.0 } => 0.0,
+        Shape::Empty => 0.0,
+    }";
+    let arm =
+        |pat: Pattern, body: ParseObj| ParseObj::Arm(Box::new(pat), Box::new(None), Box::new(body));
+    assert_eq!(
+        parse_str(expr, src.to_string()).map(|(_, e)| e),
+        Ok(ParseObj::Match(
+            Box::new(ident_obj("s")),
+            vec![
+                arm(
+                    Pattern::Variant(
+                        "Shape".to_string(),
+                        "Circle".to_string(),
+                        vec![Pattern::Binding("r".to_string())]
+                    ),
+                    binary("*", ident_obj("r"), ident_obj("r"))
+                ),
+                arm(
+                    Pattern::Struct(
+                        Box::new(path("Shape", "Rect")),
+                        vec![
+                            ("w".to_string(), Pattern::Binding("w".to_string())),
+                            (
+                                "h".to_string(),
+                                Pattern::Literal(Box::new(ParseObj::Float(0.0)))
+                            ),
+                        ],
+                        false
+                    ),
+                    ParseObj::Float(0.0)
+                ),
+                arm(
+                    Pattern::Variant("Shape".to_string(), "Empty".to_string(), vec![]),
+                    ParseObj::Float(0.0)
+                ),
+            ]
+        ))
+    );
+    assert!(parse_str(expr, "Shape::".to_string()).is_err());
 }